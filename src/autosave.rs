@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+/// When Neith saves the database on its own. Ram mode never saves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AutosavePolicy {
+    /// Only saves when `save()` is called.
+    Manual,
+    /// Saves after every successful statement.
+    EveryStatement,
+    /// Saves after the supplied number of successful statements.
+    EveryNStatements(usize),
+    /// Saves after a successful statement, once the supplied time has passed since the last save.
+    Interval(Duration),
+    /// Saves once the last handle to the database is dropped.
+    OnDrop,
+}
+
+impl Default for AutosavePolicy {
+
+    fn default() -> Self {
+        return AutosavePolicy::Manual;
+    }
+
+}
+
+/// What happened since the last save, shared between every handle to the database.
+#[derive(Debug)]
+pub struct AutosaveState {
+    /// The number of successful statements since the last save.
+    pub unsaved: usize,
+    pub last_save: Instant,
+}
+
+impl Default for AutosaveState {
+
+    fn default() -> Self {
+        return AutosaveState { unsaved: 0, last_save: Instant::now() };
+    }
+
+}
+
+impl AutosaveState {
+
+    /// Counts a successful statement.
+    ///
+    /// ## Returns
+    /// `true` if the policy wants the database saved now.
+    pub fn count_statement(&mut self, policy: &AutosavePolicy) -> bool {
+        self.unsaved += 1;
+        match policy {
+            AutosavePolicy::Manual | AutosavePolicy::OnDrop => return false,
+            AutosavePolicy::EveryStatement => return true,
+            AutosavePolicy::EveryNStatements(statements) => return self.unsaved >= *statements,
+            AutosavePolicy::Interval(interval) => return self.last_save.elapsed() >= *interval,
+        }
+    }
+
+    /// Resets the state after the database was saved.
+    pub fn saved(&mut self) {
+        self.unsaved = 0;
+        self.last_save = Instant::now();
+    }
+
+}
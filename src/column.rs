@@ -1,13 +1,18 @@
-use std::io::Error;
+use std::{io::Error, collections::HashSet};
 
 use json::JsonValue;
 
-use crate::{data::Data, success::Success};
+use crate::{data::Data, success::Success, constraint::ForeignKey, index::{Index, IndexKey, is_comparable}};
+#[cfg(feature = "parallel")]
+use crate::parallel;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Column {
     pub name: String,
     pub unique: bool,
+    pub references: Option<ForeignKey>,
+    /// Unique columns are always indexed.
+    pub index: Option<Index>,
     pub contents: ColumnData,
 }
 
@@ -21,8 +26,10 @@ impl Default for Column {
     fn default() -> Self {
         let name = String::new();
         let unique = false;
+        let references = None;
+        let index = None;
         let contents = ColumnData::default();
-        return Column{ name, unique, contents, };
+        return Column{ name, unique, references, index, contents, };
     }
 
 }
@@ -41,10 +48,14 @@ impl From<(String, bool)> for Column {
     fn from(value: (String, bool)) -> Self {
         let name = value.0;
         let unique = value.1;
+        let references = None;
         let contents = ColumnData::default();
+        let index = if unique { Some(Index::new_hash(&contents.all_row_data)) } else { None };
         return Column {
             name,
             unique,
+            references,
+            index,
             contents,
         };
     }
@@ -59,6 +70,8 @@ impl Column {
         let name = column_value.0.to_string();
         let data_object = column_value.1.clone();
         let unique = data_object["unique"].as_bool().expect("Boolean not a boolean!");
+        // Databases saved before foreign keys existed just don't have this field.
+        let references = ForeignKey::from_json_value(&data_object["references"]);
         let entry_list = data_object["entry"].members();
         let mut all_row_data: Vec<Data> = Vec::new();
         for thing in entry_list {
@@ -71,42 +84,156 @@ impl Column {
                 all_row_data.push(out);
             }
         }
-        return Column{name, unique, contents: ColumnData { all_row_data}};
+        return Column::from_saved(name, unique, references, data_object["index"].as_str(), all_row_data);
+    }
+
+    /// Creates a column out of its saved parts, and builds its index. `index_type` is the name
+    /// of the saved index, unique columns are always indexed.
+    pub fn from_saved(name: String, unique: bool, references: Option<ForeignKey>, index_type: Option<&str>, all_row_data: Vec<Data>) -> Self {
+        let index = if index_type == Some("ordered") {
+            Some(Index::new_ordered(&all_row_data))
+        } else if unique || index_type.is_some() {
+            Some(Index::new_hash(&all_row_data))
+        } else {
+            None
+        };
+        return Column{name, unique, references, index, contents: ColumnData { all_row_data}};
     }
 
     /// Creates new data from an execute function.
     pub fn new_data(&mut self, value: Data) -> Result<Success, Error> {
         if self.unique {
-            if self.contents.all_row_data.contains(&value) {
+            if self.contains(&value) {
                 return Err(Error::other(format!("This column ({:?}) is marked as unique and {:?} was found to be an entry already.", self.name, value)));
             }
         }
+        if let Some(index) = &mut self.index {
+            index.insert(&value, self.contents.all_row_data.len());
+        }
         return Ok(self.contents.new_data(value));
     }
 
     /// Deletes data from an execute function.
     pub fn delete_data(&mut self, index: usize) -> Success {
+        if let Some(column_index) = &mut self.index {
+            column_index.delete_row(&self.contents.all_row_data[index], index);
+        }
         return self.contents.delete_data(index);
     }
 
+    /// Deletes the rows, sorted in ascending order, all at once.
+    pub fn delete_rows(&mut self, rows: &[usize]) -> Success {
+        if let Some(column_index) = &mut self.index {
+            column_index.delete_rows(&self.contents.all_row_data, rows);
+        }
+        let mut row: usize = 0;
+        self.contents.all_row_data.retain(|_| {
+            let keep = rows.binary_search(&row).is_err();
+            row += 1;
+            return keep;
+        });
+        return Success::SuccessMessage(true);
+    }
+
+    /// Deletes every entry, keeping the column itself along with the kind of its index.
+    pub fn truncate(&mut self) -> Success {
+        if let Some(column_index) = &mut self.index {
+            *column_index = column_index.rebuild(&[]);
+        }
+        self.contents.all_row_data.clear();
+        return Success::SuccessMessage(true);
+    }
+
     /// Updates data from an execute function.
     pub fn update_data(&mut self, index: usize, value: Data) -> Result<Success, Error> {
         if self.unique {
-            if self.contents.all_row_data.contains(&value) {
+            if self.contains(&value) {
                 return Err(Error::other(format!("This column ({:?}) is marked as unique and {:?} was found to be an entry already.", self.name, value)));
             }
         }
+        if let Some(column_index) = &mut self.index {
+            column_index.remove(&self.contents.all_row_data[index], index);
+            column_index.insert(&value, index);
+        }
         return Ok(self.contents.update_data(index, value));
     }
 
+    /// Writes the entry of the row, or of a new row at the end, without checking if the column
+    /// is unique. Every constraint has to be checked beforehand.
+    pub fn write_data(&mut self, index: usize, value: Data) {
+        let new_row = index >= self.contents.all_row_data.len();
+        if let Some(column_index) = &mut self.index {
+            if !new_row {
+                column_index.remove(&self.contents.all_row_data[index], index);
+            }
+            column_index.insert(&value, index);
+        }
+        if new_row {
+            let _ = self.contents.new_data(value);
+        } else {
+            self.contents.all_row_data[index] = value;
+        }
+    }
+
+    /// Checks if the data is an entry of this column, using the index if there is one.
+    pub fn contains(&self, value: &Data) -> bool {
+        if let Some(index) = &self.index {
+            return index.contains(value);
+        }
+        return self.contents.all_row_data.contains(value);
+    }
+
+    /// Marks the column as unique or not. An existing index is kept, as unique columns are always
+    /// indexed.
+    ///
+    /// ## Errors
+    /// If the column should be unique, but an entry is in it more than once.
+    pub fn set_unique(&mut self, unique: bool) -> Result<Success, Error> {
+        if unique && !self.unique {
+            let mut seen: HashSet<IndexKey> = HashSet::new();
+            // A NaN is never equal to another one.
+            for data in self.contents.all_row_data.iter().filter(|data| is_comparable(data)) {
+                if !seen.insert(IndexKey(data.clone())) {
+                    return Err(Error::other(format!("This column ({:?}) can't be marked as unique, {:?} is an entry more than once.", self.name, data)));
+                }
+            }
+            let _ = self.create_index(false);
+        }
+        self.unique = unique;
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Creates a hash or ordered index for this column. An existing hash index is replaced by an
+    /// ordered one, if requested, as the ordered index can do anything the hash index can.
+    pub fn create_index(&mut self, ordered: bool) -> Success {
+        if ordered && !self.index.as_ref().is_some_and(|index| index.is_ordered()) {
+            self.index = Some(Index::new_ordered(&self.contents.all_row_data));
+        } else if self.index.is_none() {
+            self.index = Some(Index::new_hash(&self.contents.all_row_data));
+        }
+        return Success::SuccessMessage(true);
+    }
+
+    /// Rebuilds the index, if there is one, after the contents were changed directly.
+    pub fn rebuild_index(&mut self) {
+        if let Some(index) = &self.index {
+            self.index = Some(index.rebuild(&self.contents.all_row_data));
+        }
+    }
 
-    /// gets the minimum entry of a column
+    /// gets the minimum entry of a column, using the index if it is ordered
     pub fn min(&self) -> Success {
+        if let Some(out) = self.index.as_ref().and_then(|index| index.first_last(false)) {
+            return Success::Result(vec![out]);
+        }
         return self.contents.min();
     }
 
-    /// gets the maximum entry of a column
+    /// gets the maximum entry of a column, using the index if it is ordered
     pub fn max(&self) -> Success {
+        if let Some(out) = self.index.as_ref().and_then(|index| index.first_last(true)) {
+            return Success::Result(vec![out]);
+        }
         return self.contents.max();
     }
 
@@ -133,26 +260,36 @@ impl ColumnData {
         return Success::SuccessMessage(true);
     }
 
-    /// gets the minimum entry of a column
+    /// gets the minimum entry of a column, skipping entries that can't be compared like NaN.
+    /// An empty column has no minimum, it is `Null`.
     pub fn min(&self) -> Success {
-        let mut out = self.all_row_data.first().unwrap();
-        for data in self.all_row_data.iter().skip(1) {
-            if data < out {
-                out = data;
+        #[cfg(feature = "parallel")]
+        if let Some(out) = parallel::min_max(&self.all_row_data, false) {
+            return Success::Result(vec![out]);
+        }
+        let mut out: Option<&Data> = None;
+        for data in self.all_row_data.iter().filter(|data| is_comparable(data)) {
+            if out.is_none() || data < out.unwrap() {
+                out = Some(data);
             }
         }
-        return Success::Result(vec![out.to_owned()]);
+        return Success::Result(vec![out.or(self.all_row_data.first()).cloned().unwrap_or_default()]);
     }
 
-    /// gets the maximum entry of a column
+    /// gets the maximum entry of a column, skipping entries that can't be compared like NaN.
+    /// An empty column has no maximum, it is `Null`.
     pub fn max(&self) -> Success {
-        let mut out = self.all_row_data.first().unwrap();
-        for data in self.all_row_data.iter().skip(1) {
-            if data > out {
-                out = data;
+        #[cfg(feature = "parallel")]
+        if let Some(out) = parallel::min_max(&self.all_row_data, true) {
+            return Success::Result(vec![out]);
+        }
+        let mut out: Option<&Data> = None;
+        for data in self.all_row_data.iter().filter(|data| is_comparable(data)) {
+            if out.is_none() || data > out.unwrap() {
+                out = Some(data);
             }
         }
-        return Success::Result(vec![out.to_owned()]);
+        return Success::Result(vec![out.or(self.all_row_data.first()).cloned().unwrap_or_default()]);
     }
 
 }
@@ -0,0 +1,185 @@
+use std::io::Error;
+
+use json::JsonValue;
+
+use crate::{data::Data, utils::jisard::decode_data_to_jsonval};
+
+/// The operators supported between a column and its data in a condition list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Smaller,
+    SmallerEqual,
+    Larger,
+    LargerEqual,
+    /// The data has to be a list, e.g. `status in (open, closed)`.
+    In,
+}
+
+impl Operator {
+
+    /// Decodes the operator from its nql symbol.
+    ///
+    /// ## Returns
+    /// `None` if it isn't an operator.
+    pub fn from_nql(value: &str) -> Option<Self> {
+        match value {
+            "=" => return Some(Operator::Equal),
+            "!=" => return Some(Operator::NotEqual),
+            "<" => return Some(Operator::Smaller),
+            "<=" => return Some(Operator::SmallerEqual),
+            ">" => return Some(Operator::Larger),
+            ">=" => return Some(Operator::LargerEqual),
+            "in" => return Some(Operator::In),
+            _ => return None,
+        }
+    }
+
+    /// Returns the nql symbol of the operator.
+    pub fn to_nql(&self) -> String {
+        match self {
+            Operator::Equal => return "=".to_string(),
+            Operator::NotEqual => return "!=".to_string(),
+            Operator::Smaller => return "<".to_string(),
+            Operator::SmallerEqual => return "<=".to_string(),
+            Operator::Larger => return ">".to_string(),
+            Operator::LargerEqual => return ">=".to_string(),
+            Operator::In => return "in".to_string(),
+        }
+    }
+
+    /// Checks if the entry of a column fulfills the operator with the supplied data.
+    /// Entries can only be smaller or larger than data of the same type.
+    pub fn matches(&self, entry: &Data, data: &Data) -> bool {
+        match self {
+            Operator::Equal => return entry == data,
+            Operator::NotEqual => return entry != data,
+            Operator::In => {
+                if data.is_list() {
+                    return data.get_list().unwrap().contains(entry);
+                } else {
+                    return entry == data;
+                }
+            },
+            _ => {
+                if entry.get_type() != data.get_type() {
+                    return false;
+                }
+                match self {
+                    Operator::Smaller => return entry < data,
+                    Operator::SmallerEqual => return entry <= data,
+                    Operator::Larger => return entry > data,
+                    _ => return entry >= data,
+                }
+            },
+        }
+    }
+
+}
+
+/// A single entry of a decoded condition list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// `'columnname' 'operator' 'data'`
+    Compare(String, Operator, Data),
+    /// One of and / not / or / xor
+    Connector(String),
+    /// `exists (select ...)`, holding if the subquery found any rows.
+    Exists(bool),
+}
+
+impl Condition {
+
+    /// Encodes the condition as a `JsonValue`.
+    pub fn to_json_value(&self) -> JsonValue {
+        let mut out = JsonValue::new_object();
+        match self {
+            Condition::Compare(column, operator, data) => {
+                out["column"] = JsonValue::String(column.clone());
+                out["operator"] = JsonValue::String(operator.to_nql());
+                out["data"] = decode_data_to_jsonval(data.clone());
+            },
+            Condition::Connector(connector) => {
+                out["connector"] = JsonValue::String(connector.clone());
+            },
+            Condition::Exists(exists) => {
+                out["exists"] = JsonValue::Boolean(*exists);
+            },
+        }
+        return out;
+    }
+
+    /// Used for converting Json to a condition.
+    ///
+    /// ## Errors
+    /// If the json value does not describe a condition.
+    pub fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        if value["connector"].is_string() {
+            return Ok(Condition::Connector(value["connector"].as_str().unwrap().to_string()));
+        }
+        if value["exists"].is_boolean() {
+            return Ok(Condition::Exists(value["exists"].as_bool().unwrap()));
+        }
+        let column = value["column"].as_str();
+        let operator = Operator::from_nql(value["operator"].as_str().unwrap_or_default());
+        if column.is_none() || operator.is_none() {
+            return Err(Error::other(format!("Invalid condition! Value: {:?}", value)));
+        }
+        let data = if value["data"].is_array() {
+            Data::make_list(&value["data"])
+        } else {
+            Data::from_json_value(&value["data"])?
+        };
+        return Ok(Condition::Compare(column.unwrap().to_string(), operator.unwrap(), data));
+    }
+
+}
+
+/// Evaluates a decoded condition list from left to right, `compare` decides every comparison.
+///
+/// ## Returns
+/// `true` if the conditions are fulfilled, `false` otherwise.
+///
+/// ## Errors
+/// If `compare` errors, or the list is not alternating between comparisons and connectors.
+pub fn evaluate_conditions<F>(conditions: &[Condition], mut compare: F) -> Result<bool, Error> where F: FnMut(&str, &Operator, &Data) -> Result<bool, Error> {
+    let mut out = false;
+    let mut read_condition: Option<String> = None;
+    for (position, condition) in conditions.iter().enumerate() {
+        match (condition, position == 0, read_condition.clone()) {
+            (Condition::Connector(connector), false, None) => {
+                read_condition = Some(connector.to_string());
+            },
+            (Condition::Connector(_), _, _) => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+            (_, true, None) => {
+                out = evaluate_single(condition, &mut compare)?;
+            },
+            (_, false, Some(connector)) => {
+                let other = evaluate_single(condition, &mut compare)?;
+                out = match connector.as_str() {
+                    "and" => out && other,
+                    "not" => out && !other,
+                    "or" => out || other,
+                    "xor" => out ^ other,
+                    _ => return Err(Error::other("Invalid nql syntax.")),
+                };
+                read_condition = None;
+            },
+            _ => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+        }
+    }
+    if conditions.is_empty() || read_condition.is_some() {
+        return Err(Error::other("Invalid nql syntax."));
+    }
+    return Ok(out);
+}
+
+/// Evaluates a single comparison or `exists` of a condition list.
+fn evaluate_single<F>(condition: &Condition, compare: &mut F) -> Result<bool, Error> where F: FnMut(&str, &Operator, &Data) -> Result<bool, Error> {
+    match condition {
+        Condition::Compare(name, operator, data) => return compare(name, operator, data),
+        Condition::Exists(exists) => return Ok(*exists),
+        Condition::Connector(_) => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::Error;
+
+use json::JsonValue;
+
+use crate::{condition::Condition, utils::util::decode_table_column};
+
+/// What happens to the rows of a child table once the row they are referencing is deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OnDelete {
+    /// Delete the referencing rows as well.
+    Cascade,
+    /// Refuse to delete the referenced row as long as it is referenced.
+    Restrict,
+    /// Set the referencing entry to `Null`.
+    SetNull,
+}
+
+impl Default for OnDelete {
+
+    fn default() -> Self {
+        return OnDelete::Restrict;
+    }
+
+}
+
+impl OnDelete {
+
+    /// Decodes the behaviour from its nql name: `cascade`, `restrict` or `set null`.
+    ///
+    /// ## Errors
+    /// If supplied with anything else.
+    pub fn from_nql(value: &str) -> Result<Self, Error> {
+        match value.trim() {
+            "cascade" => return Ok(OnDelete::Cascade),
+            "restrict" => return Ok(OnDelete::Restrict),
+            "set null" => return Ok(OnDelete::SetNull),
+            _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be one of [cascade/restrict/set null]", value))),
+        }
+    }
+
+    /// Returns the nql name of the behaviour.
+    pub fn to_nql(&self) -> String {
+        match self {
+            OnDelete::Cascade => return "cascade".to_string(),
+            OnDelete::Restrict => return "restrict".to_string(),
+            OnDelete::SetNull => return "set null".to_string(),
+        }
+    }
+
+}
+
+/// What happens to new data if its entry of a unique column exists already:
+/// `on conflict ('columnname') {update/ignore}`
+#[derive(Clone, Debug, PartialEq)]
+pub enum OnConflict {
+    /// Update the existing row with the new data instead.
+    Update,
+    /// Keep the existing row as it is, and write nothing.
+    Ignore,
+}
+
+impl OnConflict {
+
+    /// Decodes the behaviour from its nql name: `update` or `ignore`.
+    ///
+    /// ## Errors
+    /// If supplied with anything else.
+    pub fn from_nql(value: &str) -> Result<Self, Error> {
+        match value.trim() {
+            "update" => return Ok(OnConflict::Update),
+            "ignore" => return Ok(OnConflict::Ignore),
+            _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be one of [update/ignore]", value))),
+        }
+    }
+
+}
+
+/// A column holding a foreign key points to a column of another table (or its own).
+/// Every entry that is not `Null` has to exist in the referenced column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+    pub on_delete: OnDelete,
+}
+
+impl ForeignKey {
+
+    /// Decodes a foreign key out of the remainder of a column declaration:
+    /// `references 'tablename'('columnname') on delete {cascade/restrict/set null}`
+    /// The `on delete` part is optional and defaults to `restrict`.
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql.
+    pub fn from_nql(value: &str) -> Result<Self, Error> {
+        let trimmed = value.trim();
+        let target = trimmed.strip_prefix("references ");
+        if target.is_none() {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should start with 'references'", trimmed)));
+        }
+        let split_target = target.unwrap().trim_start().splitn(2, " ");
+        let reference = split_target.clone().take(1).collect::<String>();
+        let remainder = split_target.skip(1).collect::<String>();
+        let (table, column) = decode_table_column(&reference)?;
+        let on_delete = if remainder.trim().is_empty() {
+            OnDelete::default()
+        } else {
+            let behaviour = remainder.trim().strip_prefix("on delete ");
+            if behaviour.is_none() {
+                return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'on delete {{cascade/restrict/set null}}'", remainder)));
+            }
+            OnDelete::from_nql(behaviour.unwrap())?
+        };
+        return Ok(ForeignKey { table, column, on_delete });
+    }
+
+    /// Used for converting Json to a foreign key.
+    ///
+    /// ## Returns
+    /// `None` if the json value does not describe a foreign key.
+    pub fn from_json_value(value: &JsonValue) -> Option<Self> {
+        if !value.is_object() {
+            return None;
+        }
+        let table = value["table"].as_str()?.to_string();
+        let column = value["column"].as_str()?.to_string();
+        let on_delete = OnDelete::from_nql(value["on_delete"].as_str().unwrap_or("restrict")).ok()?;
+        return Some(ForeignKey { table, column, on_delete });
+    }
+
+    /// Encodes the foreign key as a `JsonValue`.
+    pub fn to_json_value(&self) -> JsonValue {
+        let mut out = JsonValue::new_object();
+        out["table"] = JsonValue::String(self.table.clone());
+        out["column"] = JsonValue::String(self.column.clone());
+        out["on_delete"] = JsonValue::String(self.on_delete.to_nql());
+        return out;
+    }
+
+}
+
+/// A check constraint is a condition list every row of a table has to fulfill, e.g.
+/// `check positive_price [price >= 0]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub conditions: Vec<Condition>,
+}
+
+impl Check {
+
+    /// Encodes the check as a `JsonValue`.
+    pub fn to_json_value(&self) -> JsonValue {
+        let mut conditions = JsonValue::new_array();
+        for condition in &self.conditions {
+            let _ = conditions.push(condition.to_json_value());
+        }
+        let mut out = JsonValue::new_object();
+        out["name"] = JsonValue::String(self.name.clone());
+        out["conditions"] = conditions;
+        return out;
+    }
+
+    /// Used for converting Json to a check.
+    ///
+    /// ## Errors
+    /// If the json value does not describe a check.
+    pub fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        let name = value["name"].as_str();
+        if name.is_none() {
+            return Err(Error::other(format!("Check without a name! Value: {:?}", value)));
+        }
+        let mut conditions: Vec<Condition> = Vec::new();
+        for condition in value["conditions"].members() {
+            conditions.push(Condition::from_json_value(condition)?);
+        }
+        return Ok(Check { name: name.unwrap().to_string(), conditions });
+    }
+
+}
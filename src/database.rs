@@ -0,0 +1,212 @@
+use std::{fs, io::Error, path::PathBuf, sync::{Arc, Mutex, RwLock}, collections::HashMap};
+
+use crate::{table::Table, success::Success, autosave::{AutosavePolicy, AutosaveState}, format::{StorageFormat, StorageLayout}, utils::{binard, dirard, jisard::write_neithdb_file}};
+#[cfg(feature = "encryption")]
+use crate::utils::cryptard;
+
+/// How a table was last saved, as long as its `changes` didn't change since.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SavedTable {
+    /// Its encoding, written into the single file again in a `SingleFile` layout.
+    Encoded(Vec<u8>),
+    /// The file it was written to in a `TablePerFile` layout.
+    File(String),
+}
+
+/// How each table was last saved, along with its `changes` at the time. Cleared whenever the
+/// storage settings change.
+pub type SavedTables = HashMap<String, (u64, SavedTable)>;
+
+/// The name of every table in the order they were created, along with the other tables its
+/// foreign keys point to.
+pub type ForeignKeys = Vec<(String, Vec<String>)>;
+
+/// Where, how and when the database is saved.
+#[derive(Clone, Debug)]
+pub struct Storage {
+    pub path: PathBuf,
+    pub format: StorageFormat,
+    pub layout: StorageLayout,
+    pub ram_mode: bool,
+    pub autosave: AutosavePolicy,
+    // The file is encrypted with this key, if there is one.
+    #[cfg(feature = "encryption")]
+    pub key: Option<cryptard::Key>,
+    // The file is compressed before it is written.
+    #[cfg(feature = "compression")]
+    pub compression: bool,
+}
+
+impl Default for Storage {
+
+    fn default() -> Self {
+        return Storage {
+            path: PathBuf::new(),
+            format: StorageFormat::default(),
+            layout: StorageLayout::default(),
+            ram_mode: true,
+            autosave: AutosavePolicy::Manual,
+            #[cfg(feature = "encryption")]
+            key: None,
+            #[cfg(feature = "compression")]
+            compression: false,
+        };
+    }
+
+}
+
+/// Everything every handle to a database shares: the tables, along with how they are saved. A
+/// setting changed through one handle applies to all of them. It is dropped once, after the last
+/// handle is gone.
+#[derive(Debug, Default)]
+pub struct Database {
+    // putting tables on the heap, as they could grow quite large!
+    pub tables: RwLock<Vec<Arc<RwLock<Table>>>>,
+    pub storage: RwLock<Storage>,
+    pub autosave_state: Mutex<AutosaveState>,
+    pub saved_tables: Mutex<SavedTables>,
+    // Only changed while the tables are locked for writing, so it matches them while they are locked.
+    pub foreign_keys: Mutex<ForeignKeys>,
+}
+
+impl Database {
+
+    /// Creates an empty database, saved as the supplied storage says.
+    pub fn new(storage: Storage) -> Self {
+        return Database { tables: Default::default(), storage: RwLock::new(storage), autosave_state: Default::default(), saved_tables: Default::default(), foreign_keys: Default::default() };
+    }
+
+    /// Returns a copy of the storage settings.
+    ///
+    /// ## Errors
+    /// If the settings couldn't be locked.
+    pub fn get_storage(&self) -> Result<Storage, Error> {
+        let storage = self.storage.read();
+        if storage.is_ok() {
+            return Ok(storage.unwrap().clone());
+        } else {
+            return Err(Error::other("Couldn't lock storage settings!"));
+        }
+    }
+
+    /// Saves every table to disc, as laid out in the storage settings. They can't change until
+    /// every file is written.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked, or during saving to disc.
+    pub fn save(&self) -> Result<Success, json::JsonError> {
+        let storage = self.storage.read();
+        if storage.is_err() {
+            return Err(json::JsonError::wrong_type("Couldn't lock storage settings!"));
+        }
+        let ok_storage = storage.unwrap();
+        // A file left by the other layout is only removed once the new one next to it is written.
+        let converting = match ok_storage.layout {
+            StorageLayout::SingleFile => ok_storage.path.is_dir(),
+            StorageLayout::TablePerFile => ok_storage.path.is_file(),
+        };
+        let mut target = ok_storage.clone();
+        if converting {
+            target.path = ok_storage.path.with_extension("neithdb.tmp");
+        }
+        let answ = match (target.layout, target.format) {
+            (StorageLayout::SingleFile, StorageFormat::Json) => write_neithdb_file(self, &target)?,
+            (StorageLayout::SingleFile, StorageFormat::Binary) => binard::write_binary_neithdb_file(self, &target).map_err(|error| json::JsonError::WrongType(error.to_string()))?,
+            (StorageLayout::TablePerFile, _) => dirard::write_table_files(self, &target).map_err(|error| json::JsonError::WrongType(error.to_string()))?,
+        };
+        if converting {
+            let removed = match ok_storage.layout {
+                StorageLayout::SingleFile => dirard::remove_table_files(&ok_storage.path),
+                StorageLayout::TablePerFile => fs::remove_file(&ok_storage.path),
+            };
+            removed.and_then(|_| fs::rename(&target.path, &ok_storage.path)).map_err(|error| json::JsonError::WrongType(error.to_string()))?;
+        }
+        if let Ok(mut state) = self.autosave_state.lock() {
+            state.saved();
+        }
+        return Ok(answ);
+    }
+
+    /// Returns a shared handle to every table, in the order they were created.
+    /// Tables created or deleted afterwards are not part of it.
+    ///
+    /// ## Errors
+    /// If the tables couldn't be locked.
+    pub fn get_tables(&self) -> Result<Vec<Arc<RwLock<Table>>>, Error> {
+        let tables = self.tables.read();
+        if tables.is_ok() {
+            return Ok(tables.unwrap().clone());
+        } else {
+            return Err(Error::other("Couldn't lock tables!"));
+        }
+    }
+
+    /// Remembers the tables the foreign keys of the table point to now. A foreign key can only be
+    /// added while the tables are locked for writing.
+    pub fn update_foreign_keys(&self, table: &Table) {
+        if let Ok(mut foreign_keys) = self.foreign_keys.lock() {
+            if let Some(entry) = foreign_keys.iter_mut().find(|entry| entry.0 == table.name) {
+                entry.1 = table.referenced_tables();
+            }
+        }
+    }
+
+    /// Encodes every table with the supplied function, in the order they were created. A table
+    /// that didn't change since it was last encoded keeps its encoding.
+    ///
+    /// ## Returns
+    /// The name of each table, along with its encoding.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked, or encoding it failed.
+    pub fn encode_tables<F>(&self, encode: F) -> Result<Vec<(String, Vec<u8>)>, Error> where F: Fn(&Table) -> Result<Vec<u8>, Error> {
+        // The tables are read before the saved tables are locked, adding a table locks them the
+        // other way around.
+        let tables = self.get_tables()?;
+        let saved_store = self.saved_tables.lock();
+        if saved_store.is_err() {
+            return Err(Error::other("Couldn't lock saved tables!"));
+        }
+        let mut saved_tables = saved_store.unwrap();
+        let mut out: Vec<(String, Vec<u8>)> = Vec::new();
+        for entry in tables {
+            let table = entry.read();
+            if table.is_ok() {
+                let ok_table = table.unwrap();
+                let encoded = match saved_tables.get(&ok_table.name) {
+                    Some((changes, SavedTable::Encoded(encoded))) if *changes == ok_table.changes => encoded.clone(),
+                    _ => {
+                        let encoded = encode(&ok_table)?;
+                        let _ = saved_tables.insert(ok_table.name.clone(), (ok_table.changes, SavedTable::Encoded(encoded.clone())));
+                        encoded
+                    },
+                };
+                out.push((ok_table.name.clone(), encoded));
+            } else {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+        }
+        saved_tables.retain(|tablename, _| out.iter().any(|entry| &entry.0 == tablename));
+        return Ok(out);
+    }
+
+}
+
+impl Drop for Database {
+
+    // Saves unsaved statements, unless the policy is `Manual`.
+    fn drop(&mut self) {
+        let storage = self.get_storage();
+        if storage.is_err() || storage.as_ref().unwrap().ram_mode || storage.unwrap().autosave == AutosavePolicy::Manual {
+            return;
+        }
+        let unsaved = match self.autosave_state.lock() {
+            Ok(state) => state.unsaved > 0,
+            Err(_) => false,
+        };
+        if unsaved {
+            let _ = self.save();
+        }
+    }
+
+}
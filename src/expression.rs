@@ -0,0 +1,279 @@
+use std::io::Error;
+
+use crate::data::Data;
+
+/// The functions an expression can call, by name.
+const FUNCTIONS: [&str; 8] = ["upper", "lower", "length", "concat", "substr", "len", "append", "coalesce"];
+
+/// A computed entry of a select list, or the new data of an update:
+/// `count + 1`, `upper(name)`, `concat(first, ' ', last)`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    /// Data that is not computed, the value of an update written as usual.
+    Value(Data),
+    /// A word, the entry of the column with that name; or data if there is no such column.
+    Word(String),
+    /// Text in single quotes, always a string.
+    Text(String),
+    /// One of `+`, `-`, `*` or `/` between two expressions.
+    Arithmetic(Box<Expression>, char, Box<Expression>),
+    /// `functionname(argument, ...)`
+    Function(String, Vec<Expression>),
+}
+
+impl Expression {
+
+    /// Checks if anything is computed, e.g. it is more than a single word or value.
+    pub fn is_computed(&self) -> bool {
+        match self {
+            Expression::Arithmetic(..) | Expression::Function(..) => return true,
+            _ => return false,
+        }
+    }
+
+    /// Checks if the expression calls a function, or reads a column with one of the names.
+    pub fn reads_columns(&self, columnnames: &[String]) -> bool {
+        match self {
+            Expression::Word(word) => return columnnames.contains(word),
+            Expression::Arithmetic(left, _, right) => return left.reads_columns(columnnames) || right.reads_columns(columnnames),
+            Expression::Function(..) => return true,
+            _ => return false,
+        }
+    }
+
+    /// Computes the expression, `lookup` returns the entry of a column if there is a column with
+    /// that name. Any arithmetic with `Null` is `Null`, just like most functions given `Null`.
+    ///
+    /// ## Errors
+    /// If the data doesn't fit the operator or function, or during division by zero.
+    pub fn evaluate<F>(&self, lookup: &F) -> Result<Data, Error> where F: Fn(&str) -> Option<Data> {
+        match self {
+            Expression::Value(data) => return Ok(data.clone()),
+            Expression::Word(word) => return Ok(lookup(word).unwrap_or(Data::from(word.clone(), ",".to_string()))),
+            Expression::Text(text) => return Ok(Data::String(text.clone())),
+            Expression::Arithmetic(left, operator, right) => {
+                let left = left.evaluate(lookup)?;
+                let right = right.evaluate(lookup)?;
+                if left.is_null() || right.is_null() {
+                    return Ok(Data::Null());
+                }
+                if !left.is_float() || !right.is_float() {
+                    return Err(Error::other(format!("Can only compute {:?} {} {:?} with numbers.", left, operator, right)));
+                }
+                let (left, right) = (left.get_float().unwrap(), right.get_float().unwrap());
+                match operator {
+                    '+' => return Ok(Data::Float(left + right)),
+                    '-' => return Ok(Data::Float(left - right)),
+                    '*' => return Ok(Data::Float(left * right)),
+                    _ if right == 0.0 => return Err(Error::other(format!("Division by zero! {} / {}", left, right))),
+                    _ => return Ok(Data::Float(left / right)),
+                }
+            },
+            Expression::Function(name, arguments) => {
+                let mut data: Vec<Data> = Vec::new();
+                for argument in arguments {
+                    data.push(argument.evaluate(lookup)?);
+                }
+                return call_function(name, data);
+            },
+        }
+    }
+
+}
+
+/// Decodes an expression. Operators have to be separated by whitespace, e.g. `count - 1`, as
+/// `count-1` is a single word. Parenthesis group expressions, or hold the arguments of a function.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_expression(value: &str) -> Result<Expression, Error> {
+    let tokens = tokenize(value)?;
+    let mut parser = Parser { tokens, position: 0 };
+    let out = parser.parse_sum()?;
+    if parser.position != parser.tokens.len() {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} is not an expression.", value)));
+    }
+    return Ok(out);
+}
+
+/// Decodes the new data of an update. Data that is computed from a column of the table, or by
+/// a function, becomes an expression; anything else stays the data it was decoded as, so text
+/// like `555 - 1234` is written as it is. Text starting with a single quote is never computed,
+/// `'total - tax'` is written as it is even if there are such columns.
+pub fn decode_assignments(value: Vec<(String, Data)>, columnnames: &[String]) -> Vec<(String, Expression)> {
+    let mut out: Vec<(String, Expression)> = Vec::new();
+    for (columnname, data) in value {
+        let expression = data.get_string().filter(|text| !text.trim_start().starts_with('\'')).and_then(|text| decode_expression(&text).ok()).filter(|expression| expression.is_computed() && expression.reads_columns(columnnames));
+        match expression {
+            Some(expression) => out.push((columnname, expression)),
+            None => out.push((columnname, Expression::Value(data))),
+        }
+    }
+    return out;
+}
+
+/// A single piece of an expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Comma,
+    Text(String),
+    Word(String),
+}
+
+/// Splits an expression into its tokens.
+///
+/// ## Errors
+/// If a text in single quotes is never closed.
+fn tokenize(value: &str) -> Result<Vec<Token>, Error> {
+    let mut out: Vec<Token> = Vec::new();
+    let mut characters = value.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '(' => out.push(Token::Open),
+            ')' => out.push(Token::Close),
+            ',' => out.push(Token::Comma),
+            '\'' => {
+                let mut text = String::new();
+                loop {
+                    match characters.next() {
+                        Some('\'') => break,
+                        Some(character) => text.push(character),
+                        None => return Err(Error::other(format!("Invalid nql syntax. {:?} is missing a closing '.", value))),
+                    }
+                }
+                out.push(Token::Text(text));
+            },
+            _ if character.is_whitespace() => {},
+            _ => {
+                let mut word = character.to_string();
+                while let Some(next) = characters.peek() {
+                    if next.is_whitespace() || ['(', ')', ',', '\''].contains(next) {
+                        break;
+                    }
+                    word.push(characters.next().unwrap());
+                }
+                out.push(Token::Word(word));
+            },
+        }
+    }
+    return Ok(out);
+}
+
+/// Decodes the tokens of an expression, `*` and `/` before `+` and `-`, otherwise left to right.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position);
+    }
+
+    /// Returns the operator at the current position, if it is one of the supplied ones.
+    fn operator(&self, operators: &[char]) -> Option<char> {
+        match self.peek() {
+            Some(Token::Word(word)) if word.len() == 1 && operators.contains(&word.chars().next().unwrap()) => return word.chars().next(),
+            _ => return None,
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<Expression, Error> {
+        let mut out = self.parse_product()?;
+        while let Some(operator) = self.operator(&['+', '-']) {
+            self.position += 1;
+            out = Expression::Arithmetic(Box::new(out), operator, Box::new(self.parse_product()?));
+        }
+        return Ok(out);
+    }
+
+    fn parse_product(&mut self) -> Result<Expression, Error> {
+        let mut out = self.parse_single()?;
+        while let Some(operator) = self.operator(&['*', '/']) {
+            self.position += 1;
+            out = Expression::Arithmetic(Box::new(out), operator, Box::new(self.parse_single()?));
+        }
+        return Ok(out);
+    }
+
+    fn parse_single(&mut self) -> Result<Expression, Error> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Open) => {
+                let out = self.parse_sum()?;
+                self.expect_close()?;
+                return Ok(out);
+            },
+            Some(Token::Text(text)) => return Ok(Expression::Text(text)),
+            Some(Token::Word(word)) if FUNCTIONS.contains(&word.as_str()) && self.peek() == Some(&Token::Open) => {
+                self.position += 1;
+                let mut arguments: Vec<Expression> = Vec::new();
+                if self.peek() != Some(&Token::Close) {
+                    arguments.push(self.parse_sum()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        arguments.push(self.parse_sum()?);
+                    }
+                }
+                self.expect_close()?;
+                return Ok(Expression::Function(word, arguments));
+            },
+            Some(Token::Word(word)) if !["+", "-", "*", "/"].contains(&word.as_str()) => return Ok(Expression::Word(word)),
+            _ => return Err(Error::other(format!("Invalid nql syntax. Expected a column, data or function at {:?}.", token))),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), Error> {
+        if self.peek() != Some(&Token::Close) {
+            return Err(Error::other(format!("Invalid nql syntax. Expected ')' at {:?}.", self.peek())));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+}
+
+/// Calls the function with the computed arguments.
+///
+/// ## Errors
+/// If there is no such function, or the arguments don't fit it.
+fn call_function(name: &str, arguments: Vec<Data>) -> Result<Data, Error> {
+    let wrong_arguments = || Error::other(format!("Wrong arguments for {}(): {:?}", name, arguments));
+    match (name, arguments.as_slice()) {
+        ("coalesce", _) => return Ok(arguments.iter().find(|data| !data.is_null()).cloned().unwrap_or_default()),
+        (_, [Data::Null(), ..]) if name != "concat" => return Ok(Data::Null()),
+        ("upper", [Data::String(text)]) => return Ok(Data::String(text.to_uppercase())),
+        ("lower", [Data::String(text)]) => return Ok(Data::String(text.to_lowercase())),
+        ("length", [Data::String(text)]) => return Ok(Data::Float(text.chars().count() as f64)),
+        ("concat", _) if !arguments.is_empty() => {
+            let mut out = String::new();
+            for data in &arguments {
+                match data {
+                    Data::String(text) => out.push_str(text),
+                    Data::Float(float) => out.push_str(&float.to_string()),
+                    Data::Bool(boolean) => out.push_str(&boolean.to_string()),
+                    Data::Null() => return Ok(Data::Null()),
+                    Data::List(_) => return Err(wrong_arguments()),
+                }
+            }
+            return Ok(Data::String(out));
+        },
+        ("substr", [Data::String(text), Data::Float(start)]) => {
+            return Ok(Data::String(text.chars().skip((*start as usize).saturating_sub(1)).collect()));
+        },
+        ("substr", [Data::String(text), Data::Float(start), Data::Float(length)]) => {
+            return Ok(Data::String(text.chars().skip((*start as usize).saturating_sub(1)).take(*length as usize).collect()));
+        },
+        ("len", [Data::List(list)]) => return Ok(Data::Float(list.len() as f64)),
+        ("append", [Data::List(list), data]) => {
+            let mut out = list.clone();
+            out.push(data.clone());
+            return Ok(Data::List(out));
+        },
+        _ => return Err(wrong_arguments()),
+    }
+}
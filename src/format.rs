@@ -0,0 +1,37 @@
+/// The file formats a database can be saved in. Both use the `.neithdb` extension, connecting
+/// detects the format of an existing file on its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageFormat {
+    /// A json object of tables, readable by humans and other programs. This is the default.
+    Json,
+    /// A compact binary format, faster to read and write. Every table is stored with a checksum,
+    /// and floats are saved exactly.
+    Binary,
+}
+
+impl Default for StorageFormat {
+
+    fn default() -> Self {
+        return StorageFormat::Json;
+    }
+
+}
+
+/// How a database is laid out on disc.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageLayout {
+    /// A single `.neithdb` file holding every table, rewritten during every save. This is the
+    /// default.
+    SingleFile,
+    /// A `.neithdb` directory holding a file for each table, and a manifest listing them. A save
+    /// only writes the tables that changed since the last one.
+    TablePerFile,
+}
+
+impl Default for StorageLayout {
+
+    fn default() -> Self {
+        return StorageLayout::SingleFile;
+    }
+
+}
@@ -0,0 +1,411 @@
+use std::{collections::{HashMap, BTreeMap}, hash::{Hash, Hasher}, cmp::Ordering};
+
+use crate::{data::Data, condition::Operator};
+
+/// Wraps `Data` so it can be used as the key of an index.
+/// Floats are compared by value, with `0.0 == -0.0` and `NaN == NaN`, so rows holding a NaN can be
+/// found again when they change. Looking up a NaN finds nothing, just like comparing with `=`.
+/// Keys are ordered just like `Data`: first by type (List, Float, Bool, String, Null), then by value.
+#[derive(Clone, Debug)]
+pub struct IndexKey(pub Data);
+
+impl PartialEq for IndexKey {
+
+    fn eq(&self, other: &Self) -> bool {
+        fn equal(data: &Data, other: &Data) -> bool {
+            match (data, other) {
+                (Data::Float(one), Data::Float(two)) => return one == two || (one.is_nan() && two.is_nan()),
+                (Data::List(one), Data::List(two)) => return one.len() == two.len() && one.iter().zip(two).all(|(one, two)| equal(one, two)),
+                _ => return data == other,
+            }
+        }
+        return equal(&self.0, &other.0);
+    }
+
+}
+
+impl Eq for IndexKey {}
+
+impl PartialOrd for IndexKey {
+
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+
+}
+
+impl Ord for IndexKey {
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn rank(data: &Data) -> u8 {
+            match data {
+                Data::List(_) => return 0,
+                Data::Float(_) => return 1,
+                Data::Bool(_) => return 2,
+                Data::String(_) => return 3,
+                Data::Null() => return 4,
+            }
+        }
+        fn compare(data: &Data, other: &Data) -> Ordering {
+            match (data, other) {
+                (Data::List(one), Data::List(two)) => {
+                    for (one, two) in one.iter().zip(two) {
+                        let out = compare(one, two);
+                        if out != Ordering::Equal {
+                            return out;
+                        }
+                    }
+                    return one.len().cmp(&two.len());
+                },
+                (Data::Float(one), Data::Float(two)) => {
+                    if one == two {
+                        return Ordering::Equal;
+                    }
+                    return one.total_cmp(two);
+                },
+                (Data::Bool(one), Data::Bool(two)) => return one.cmp(two),
+                (Data::String(one), Data::String(two)) => return one.cmp(two),
+                _ => return rank(data).cmp(&rank(other)),
+            }
+        }
+        return compare(&self.0, &other.0);
+    }
+
+}
+
+impl Hash for IndexKey {
+
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn hash_data<H: Hasher>(data: &Data, state: &mut H) {
+            data.get_type().hash(state);
+            match data {
+                Data::List(list) => {
+                    list.len().hash(state);
+                    for entry in list {
+                        hash_data(entry, state);
+                    }
+                },
+                Data::Float(float) => {
+                    // -0.0 and 0.0 are equal, so they need the same hash.
+                    if *float == 0.0 {
+                        0.0_f64.to_bits().hash(state);
+                    } else {
+                        float.to_bits().hash(state);
+                    }
+                },
+                Data::Bool(boolean) => boolean.hash(state),
+                Data::String(string) => string.hash(state),
+                Data::Null() => {},
+            }
+        }
+        hash_data(&self.0, state);
+    }
+
+}
+
+/// An in-memory index of a column, pointing from each entry to the rows containing it.
+/// It is never saved, only rebuilt from the column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Index {
+    /// Used for `=` and `in` lookups.
+    Hash(HashMap<IndexKey, Vec<usize>>),
+    /// Used for `=`, `in`, `<`, `<=`, `>` and `>=` lookups, min, max and sorting.
+    Ordered(BTreeMap<IndexKey, Vec<usize>>),
+}
+
+impl Index {
+
+    /// Creates a new hash index over the supplied column data.
+    pub fn new_hash(all_row_data: &[Data]) -> Self {
+        let mut out = Index::Hash(HashMap::new());
+        for (row, data) in all_row_data.iter().enumerate() {
+            out.insert(data, row);
+        }
+        return out;
+    }
+
+    /// Creates a new ordered index over the supplied column data.
+    pub fn new_ordered(all_row_data: &[Data]) -> Self {
+        let mut out = Index::Ordered(BTreeMap::new());
+        for (row, data) in all_row_data.iter().enumerate() {
+            out.insert(data, row);
+        }
+        return out;
+    }
+
+    /// Creates a new index of the same kind over the supplied column data.
+    pub fn rebuild(&self, all_row_data: &[Data]) -> Self {
+        match self {
+            Index::Hash(_) => return Index::new_hash(all_row_data),
+            Index::Ordered(_) => return Index::new_ordered(all_row_data),
+        }
+    }
+
+    /// Returns the name of the kind of index.
+    pub fn get_type(&self) -> String {
+        match self {
+            Index::Hash(_) => return "hash".to_string(),
+            Index::Ordered(_) => return "ordered".to_string(),
+        }
+    }
+
+    /// Checks if this is an ordered index.
+    pub fn is_ordered(&self) -> bool {
+        return matches!(self, Index::Ordered(_));
+    }
+
+    /// Adds the row to the entries of the data.
+    pub fn insert(&mut self, data: &Data, row: usize) {
+        match self {
+            Index::Hash(map) => map.entry(IndexKey(data.clone())).or_default().push(row),
+            Index::Ordered(map) => map.entry(IndexKey(data.clone())).or_default().push(row),
+        }
+    }
+
+    /// Removes the row from the entries of the data.
+    pub fn remove(&mut self, data: &Data, row: usize) {
+        let key = IndexKey(data.clone());
+        let rows = match self {
+            Index::Hash(map) => map.get_mut(&key),
+            Index::Ordered(map) => map.get_mut(&key),
+        };
+        if let Some(rows) = rows {
+            rows.retain(|entry| *entry != row);
+            if rows.is_empty() {
+                match self {
+                    Index::Hash(map) => { let _ = map.remove(&key); },
+                    Index::Ordered(map) => { let _ = map.remove(&key); },
+                }
+            }
+        }
+    }
+
+    /// Removes the row of the data, and moves every row after it up by one; just like removing
+    /// the row from the column does.
+    pub fn delete_row(&mut self, data: &Data, row: usize) {
+        self.remove(data, row);
+        let all_rows: Vec<&mut Vec<usize>> = match self {
+            Index::Hash(map) => map.values_mut().collect(),
+            Index::Ordered(map) => map.values_mut().collect(),
+        };
+        for rows in all_rows {
+            for entry in rows.iter_mut() {
+                if *entry > row {
+                    *entry -= 1;
+                }
+            }
+        }
+    }
+
+    /// Removes the rows, sorted in ascending order, and moves every row after them up; just like
+    /// removing the rows from the column does. Each remaining row is only moved once.
+    pub fn delete_rows(&mut self, all_row_data: &[Data], rows: &[usize]) {
+        for row in rows {
+            self.remove(&all_row_data[*row], *row);
+        }
+        let all_rows: Vec<&mut Vec<usize>> = match self {
+            Index::Hash(map) => map.values_mut().collect(),
+            Index::Ordered(map) => map.values_mut().collect(),
+        };
+        for entries in all_rows {
+            for entry in entries.iter_mut() {
+                // Moved up by one for every deleted row in front of it.
+                *entry -= rows.partition_point(|row| row < entry);
+            }
+        }
+    }
+
+    /// Checks if the data is an entry of the column.
+    pub fn contains(&self, data: &Data) -> bool {
+        if !is_comparable(data) {
+            return false;
+        }
+        match self {
+            Index::Hash(map) => return map.contains_key(&IndexKey(data.clone())),
+            Index::Ordered(map) => return map.contains_key(&IndexKey(data.clone())),
+        }
+    }
+
+    /// Checks if the index points from the entry to the row, a NaN included.
+    pub fn points_to(&self, data: &Data, row: usize) -> bool {
+        let rows = match self {
+            Index::Hash(map) => map.get(&IndexKey(data.clone())),
+            Index::Ordered(map) => map.get(&IndexKey(data.clone())),
+        };
+        return rows.is_some_and(|rows| rows.contains(&row));
+    }
+
+    /// Returns how many rows the index points to.
+    pub fn row_count(&self) -> usize {
+        match self {
+            Index::Hash(map) => return map.values().map(|rows| rows.len()).sum(),
+            Index::Ordered(map) => return map.values().map(|rows| rows.len()).sum(),
+        }
+    }
+
+    /// Estimates how many of the rows of the column hold the data, without reading them: none
+    /// if it isn't an entry, else the rows are assumed to be spread evenly over the entries.
+    pub fn estimate_equal(&self, data: &Data, rows: usize) -> usize {
+        let entries = match self {
+            Index::Hash(map) => map.len(),
+            Index::Ordered(map) => map.len(),
+        };
+        if !self.contains(data) {
+            return 0;
+        }
+        return rows.div_ceil(entries);
+    }
+
+    /// Estimates the share of the rows fulfilling one of the operators `<`, `<=`, `>` or `>=`
+    /// with a number, from where it lies between the smallest and largest entry.
+    ///
+    /// ## Returns
+    /// `None` if this is not an ordered index, it is not one of the operators, or the data or
+    /// the smallest and largest entry aren't numbers.
+    pub fn estimate_range(&self, operator: &Operator, data: &Data) -> Option<f64> {
+        let value = match data {
+            Data::Float(value) if !value.is_nan() => *value,
+            _ => return None,
+        };
+        let (first, last) = match (self.first_last(false)?, self.first_last(true)?) {
+            (Data::Float(first), Data::Float(last)) => (first, last),
+            _ => return None,
+        };
+        let below = if last > first { ((value - first) / (last - first)).clamp(0.0, 1.0) } else if value > first { 1.0 } else { 0.0 };
+        match operator {
+            Operator::Smaller | Operator::SmallerEqual => return Some(below),
+            Operator::Larger | Operator::LargerEqual => return Some(1.0 - below),
+            _ => return None,
+        }
+    }
+
+    /// Returns the rows containing the data, in ascending order.
+    pub fn get(&self, data: &Data) -> Vec<usize> {
+        if !is_comparable(data) {
+            return Vec::new();
+        }
+        let mut out = match self {
+            Index::Hash(map) => map.get(&IndexKey(data.clone())).cloned().unwrap_or_default(),
+            Index::Ordered(map) => map.get(&IndexKey(data.clone())).cloned().unwrap_or_default(),
+        };
+        out.sort();
+        return out;
+    }
+
+    /// Returns the rows with entries of the same type as the data, fulfilling one of the
+    /// operators `<`, `<=`, `>` or `>=`, in ascending order.
+    ///
+    /// ## Returns
+    /// `None` if this is not an ordered index or it is not one of the operators.
+    pub fn range(&self, operator: &Operator, data: &Data) -> Option<Vec<usize>> {
+        let map = match self {
+            Index::Ordered(map) => map,
+            _ => return None,
+        };
+        let key = IndexKey(data.clone());
+        let mut out: Vec<usize> = Vec::new();
+        match operator {
+            Operator::Smaller | Operator::SmallerEqual => {
+                for (entry, rows) in map.range(..=key.clone()).rev() {
+                    if entry.0.get_type() != data.get_type() {
+                        break;
+                    }
+                    // A NaN is neither smaller nor larger than anything.
+                    if operator.matches(&entry.0, data) {
+                        out.extend(rows);
+                    }
+                }
+            },
+            Operator::Larger | Operator::LargerEqual => {
+                for (entry, rows) in map.range(key.clone()..) {
+                    if entry.0.get_type() != data.get_type() {
+                        break;
+                    }
+                    if operator.matches(&entry.0, data) {
+                        out.extend(rows);
+                    }
+                }
+            },
+            _ => return None,
+        }
+        out.sort();
+        return Some(out);
+    }
+
+    /// Returns the smallest or largest entry. Entries that can't be compared, like NaN, are
+    /// skipped, just like reading every row does.
+    ///
+    /// ## Returns
+    /// `None` if this is not an ordered index or it has no comparable entry.
+    pub fn first_last(&self, last: bool) -> Option<Data> {
+        match self {
+            Index::Ordered(map) if last => return map.keys().rev().find(|entry| is_comparable(&entry.0)).map(|entry| entry.0.clone()),
+            Index::Ordered(map) => return map.keys().find(|entry| is_comparable(&entry.0)).map(|entry| entry.0.clone()),
+            _ => return None,
+        }
+    }
+
+    /// Returns every row in the order of its entry, rows with equal entries stay in ascending
+    /// order. Rows with entries that can't be compared, like NaN, come last in either direction,
+    /// in ascending order; just like sorting without the index.
+    ///
+    /// ## Returns
+    /// `None` if this is not an ordered index.
+    pub fn sorted_rows(&self, descending: bool) -> Option<Vec<usize>> {
+        let map = match self {
+            Index::Ordered(map) => map,
+            _ => return None,
+        };
+        let mut out: Vec<usize> = Vec::new();
+        let mut incomparable: Vec<usize> = Vec::new();
+        let entries: Vec<(&IndexKey, &Vec<usize>)> = if descending { map.iter().rev().collect() } else { map.iter().collect() };
+        for (entry, rows) in entries {
+            if !is_comparable(&entry.0) {
+                incomparable.extend(rows);
+                continue;
+            }
+            let mut sorted_rows = rows.clone();
+            sorted_rows.sort();
+            out.extend(sorted_rows);
+        }
+        incomparable.sort();
+        out.extend(incomparable);
+        return Some(out);
+    }
+
+}
+
+/// Checks if the data is equal to itself, which a NaN, even inside of a list, is not.
+pub fn is_comparable(data: &Data) -> bool {
+    return data.partial_cmp(data).is_some();
+}
+
+/// How the rows fulfilling a single condition are found.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Access {
+    /// Looking up the entries in a hash index.
+    HashLookup,
+    /// Looking up the entries in an ordered index.
+    OrderedLookup,
+    /// Reading a range of entries out of an ordered index.
+    OrderedRange,
+    /// Reading every entry of the column.
+    FullScan,
+    /// Nothing is read, the result of a subquery decides.
+    Subquery,
+}
+
+impl Access {
+
+    /// Returns a short description of the access, as shown by `explain`.
+    pub fn describe(&self) -> String {
+        match self {
+            Access::HashLookup => return "hash index lookup".to_string(),
+            Access::OrderedLookup => return "ordered index lookup".to_string(),
+            Access::OrderedRange => return "ordered index range".to_string(),
+            Access::FullScan => return "full scan".to_string(),
+            Access::Subquery => return "subquery".to_string(),
+        }
+    }
+
+}
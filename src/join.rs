@@ -0,0 +1,248 @@
+use std::{io::Error, cmp::Ordering};
+
+use crate::{data::Data, table::Table, index::Index, condition::{Condition, evaluate_conditions}, expression::{Expression, decode_expression}, success::{Success, ResultSet}, utils::util::{strip_leading_word, strip_alias}};
+
+/// Which rows of the joined table are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JoinKind {
+    /// Only rows with a match in both tables.
+    Inner,
+    /// Every row of the tables joined so far, with `Null` for the joined table if it has no match.
+    Left,
+}
+
+/// A table joined to the ones before it: `{left} join 'tablename' on 'a.column' = 'b.column'`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: String,
+    pub on: (String, String),
+}
+
+/// Checks if the remainder of a select statement following the first table name starts with a
+/// join.
+pub fn starts_with_join(value: &str) -> bool {
+    let mut words = value.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("join"), _) => return true,
+        (Some("inner"), Some("join")) | (Some("left"), Some("join")) => return true,
+        _ => return false,
+    }
+}
+
+/// Decodes every join following the first table name of a select statement, e.g.
+/// `join b on a.id = b.a_id left join c on b.id = c.b_id where [...]`
+///
+/// ## Returns
+/// A touple [`(Vec<Join>, String)`] of the joins, followed by the rest of the statement.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_joins(value: String) -> Result<(Vec<Join>, String), Error> {
+    let mut out: Vec<Join> = Vec::new();
+    let mut remainder = value.trim().to_string();
+    while starts_with_join(&remainder) {
+        let command_lvl1 = strip_leading_word(remainder);
+        let (kind, command_lvl2) = match command_lvl1.0.as_str() {
+            "left" => (JoinKind::Left, strip_leading_word(command_lvl1.1).1),
+            "inner" => (JoinKind::Inner, strip_leading_word(command_lvl1.1).1),
+            _ => (JoinKind::Inner, command_lvl1.1),
+        };
+        let command_lvl3 = strip_leading_word(command_lvl2);
+        let on: Vec<String> = command_lvl3.1.split_whitespace().take(4).map(|word| word.to_string()).collect();
+        if on.len() < 4 || on[0] != "on" || on[2] != "=" {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'on table.column = other_table.column'", command_lvl3.1)));
+        }
+        out.push(Join { kind, table: command_lvl3.0, on: (on[1].clone(), on[3].clone()) });
+        remainder = command_lvl3.1.split_whitespace().skip(4).collect::<Vec<&str>>().join(" ");
+    }
+    return Ok((out, remainder));
+}
+
+/// The tables of a join, in the order they are written. Every row of a join holds a row of each
+/// table, or `None` if a left join found no match.
+pub struct JoinedTables<'a> {
+    pub tables: Vec<&'a Table>,
+}
+
+impl JoinedTables<'_> {
+
+    /// Finds a column written as `tablename.columnname`. A column name without its table is
+    /// found as well, as long as only one of the tables has it.
+    ///
+    /// ## Returns
+    /// A touple [`(usize, usize)`] of the position of the table and the index of the column.
+    ///
+    /// ## Errors
+    /// If there is no such column, or it is in more than one table.
+    pub fn search_for_column(&self, name: &str) -> Result<(usize, usize), Error> {
+        if let Some((tablename, columnname)) = name.split_once(".") {
+            for (position, table) in self.tables.iter().enumerate() {
+                if table.name == tablename {
+                    return Ok((position, table.search_for_column(columnname.to_string())?));
+                }
+            }
+            return Err(Error::other(format!("Table with name {} is not part of the join.", tablename)));
+        }
+        let mut found: Vec<(usize, usize)> = Vec::new();
+        for (position, table) in self.tables.iter().enumerate() {
+            if let Ok(column_index) = table.search_for_column(name.to_string()) {
+                found.push((position, column_index));
+            }
+        }
+        match found.as_slice() {
+            [column] => return Ok(*column),
+            [] => return Err(Error::other(format!("Column with name '{}' not found.", name))),
+            _ => return Err(Error::other(format!("Column with name '{}' is ambiguous, write it as 'tablename.{}'.", name, name))),
+        }
+    }
+
+    /// Returns the name of the column, prefixed by the name of its table.
+    pub fn qualified_name(&self, column: (usize, usize)) -> String {
+        let table = self.tables[column.0];
+        return format!("{}.{}", table.name, table.columns[column.1].name);
+    }
+
+    /// Returns the entry of the column in the joined row, `Null` if a left join found no match.
+    pub fn get_data(&self, row: &[Option<usize>], column: (usize, usize)) -> Data {
+        match row[column.0] {
+            Some(table_row) => return self.tables[column.0].columns[column.1].contents.all_row_data[table_row].clone(),
+            None => return Data::Null(),
+        }
+    }
+
+    /// Joins every table to the ones before it, starting with every row of the first table.
+    /// Entries of the joined table are looked up in its index if it has one, otherwise an index
+    /// is built for the duration of the join. `Null` never matches anything.
+    ///
+    /// ## Returns
+    /// The joined rows, in the order of the rows of the first table.
+    ///
+    /// ## Errors
+    /// If a column of a join doesn't exist, or neither of them is part of the joined table.
+    pub fn join_rows(&self, joins: &[Join]) -> Result<Vec<Vec<Option<usize>>>, Error> {
+        let mut out: Vec<Vec<Option<usize>>> = self.tables[0].select_all_rows().into_iter().map(|row| vec![Some(row)]).collect();
+        for (counter, join) in joins.iter().enumerate() {
+            let position = counter + 1;
+            let one = self.search_for_column(&join.on.0)?;
+            let two = self.search_for_column(&join.on.1)?;
+            let (outer, inner) = if two.0 == position && one.0 < position {
+                (one, two)
+            } else if one.0 == position && two.0 < position {
+                (two, one)
+            } else {
+                return Err(Error::other(format!("Invalid nql syntax. 'on {} = {}' should compare a column of {} with one of the tables before it.", join.on.0, join.on.1, join.table)));
+            };
+            let column = &self.tables[position].columns[inner.1];
+            let built: Index;
+            let index = match &column.index {
+                Some(index) => index,
+                None => {
+                    built = Index::new_hash(&column.contents.all_row_data);
+                    &built
+                },
+            };
+            let mut joined: Vec<Vec<Option<usize>>> = Vec::new();
+            for row in out {
+                let data = self.get_data(&row, outer);
+                let matches = if data.is_null() { Vec::new() } else { index.get(&data) };
+                if matches.is_empty() && join.kind == JoinKind::Left {
+                    let mut new_row = row.clone();
+                    new_row.push(None);
+                    joined.push(new_row);
+                }
+                for table_row in matches {
+                    let mut new_row = row.clone();
+                    new_row.push(Some(table_row));
+                    joined.push(new_row);
+                }
+            }
+            out = joined;
+        }
+        return Ok(out);
+    }
+
+    /// Keeps the joined rows fulfilling a decoded condition list, every comparison names a column
+    /// like a select list does.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or the list is not alternating between comparisons and
+    /// connectors.
+    pub fn filter_rows(&self, rows: Vec<Vec<Option<usize>>>, conditions: &[Condition]) -> Result<Vec<Vec<Option<usize>>>, Error> {
+        let mut out: Vec<Vec<Option<usize>>> = Vec::new();
+        for row in rows {
+            let matches = evaluate_conditions(conditions, |name, operator, data| {
+                return Ok(operator.matches(&self.get_data(&row, self.search_for_column(name)?), data));
+            })?;
+            if matches {
+                out.push(row);
+            }
+        }
+        return Ok(out);
+    }
+
+    /// Sorts the joined rows by a column, rows with equal entries keep their order.
+    ///
+    /// ## Errors
+    /// If the column doesn't exist.
+    pub fn order_rows(&self, mut rows: Vec<Vec<Option<usize>>>, columnname: String, descending: bool) -> Result<Vec<Vec<Option<usize>>>, Error> {
+        let column = self.search_for_column(&columnname)?;
+        rows.sort_by(|one, two| {
+            let order = self.get_data(one, column).partial_cmp(&self.get_data(two, column)).unwrap_or(Ordering::Equal);
+            if descending { order.reverse() } else { order }
+        });
+        return Ok(rows);
+    }
+
+    /// Selects the columns out of the joined rows, `*` selects every column of every table.
+    /// Entries can be computed as well, e.g. `concat(authors.name, ': ', books.title)`, and
+    /// renamed: `books.title as title`.
+    ///
+    /// ## Returns
+    /// A `ResultSet` naming every entry by its alias, every other column as
+    /// `tablename.columnname` and every other computed entry as it was written, in the order they
+    /// were selected.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or an entry couldn't be computed.
+    pub fn select_data(&self, column_names: Vec<String>, rows: Vec<Vec<Option<usize>>>) -> Result<Success, Error> {
+        let mut entries: Vec<(String, Expression)> = Vec::new();
+        if column_names.contains(&"*".to_string()) {
+            for (position, table) in self.tables.iter().enumerate() {
+                for column_index in 0..table.columns.len() {
+                    let name = self.qualified_name((position, column_index));
+                    entries.push((name.clone(), Expression::Word(name)));
+                }
+            }
+        } else {
+            for entry in &column_names {
+                let (name, alias) = strip_alias(entry);
+                match self.search_for_column(&name) {
+                    Ok(column) => {
+                        let name = self.qualified_name(column);
+                        entries.push((alias.unwrap_or(name.clone()), Expression::Word(name)));
+                    },
+                    Err(error) => {
+                        let expression = decode_expression(&name).ok().filter(|expression| expression.is_computed());
+                        if expression.is_none() {
+                            return Err(error);
+                        }
+                        entries.push((alias.unwrap_or(name), expression.unwrap()));
+                    },
+                }
+            }
+        }
+        let mut out: Vec<(String, Vec<Data>)> = Vec::new();
+        for (name, expression) in entries {
+            let mut data: Vec<Data> = Vec::new();
+            for row in &rows {
+                data.push(expression.evaluate(&|columnname| {
+                    return self.search_for_column(columnname).ok().map(|column| self.get_data(row, column));
+                })?);
+            }
+            out.push((name, data));
+        }
+        return Ok(Success::ResultSet(ResultSet::from(out)));
+    }
+
+}
@@ -1,13 +1,34 @@
-use std::{io::{self, Error}, path::{Path, PathBuf}, time::Instant, sync::{Mutex, MutexGuard}, rc::Rc};
+use std::{io::{self, Error}, path::{Path, PathBuf}, time::Instant, sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, LockResult}, collections::{HashMap, HashSet}};
 
 use chrono;
 
-use crate::{utils::util::{*}, data::Data};
+use crate::{utils::util::{*}, column::Column, constraint::{ForeignKey, OnDelete, OnConflict}, condition::Condition, index::IndexKey};
 
 #[cfg(test)]
 mod tests;
 
 mod success;
+// Constraints placed on columns and tables
+mod constraint;
+// The conditions used in condition lists
+mod condition;
+// The in-memory column indices
+mod index;
+// Selecting from tables joined together
+mod join;
+// Computed expressions of select lists and updates
+mod expression;
+// Describing tables and their columns
+mod schema;
+// Splitting queries between threads
+#[cfg(feature = "parallel")]
+mod parallel;
+// When to save on its own
+mod autosave;
+// The state shared by every handle to a database
+mod database;
+// The file formats to save in
+mod format;
 // The column representation
 mod column;
 // The table representation
@@ -19,34 +40,44 @@ mod utils;
 
 use crate::table::Table;
 use crate::utils::jisard;
-use jisard::read_json_from_neithdb_file;
-use utils::jisard::write_neithdb_file;
-use success::Success;
+use jisard::{read_neithdb_bytes, decompress_neithdb_bytes};
+use success::{Success, ResultSet};
+use join::{JoinedTables, starts_with_join, decode_joins};
+use expression::decode_assignments;
+use schema::show_tables;
+use database::{Database, ForeignKeys, SavedTables, Storage};
+pub use autosave::AutosavePolicy;
+pub use format::{StorageFormat, StorageLayout};
+pub use data::Data;
+pub use schema::{TableSchema, ColumnSchema};
+use utils::dirard;
+#[cfg(feature = "encryption")]
+use utils::cryptard;
+#[cfg(feature = "compression")]
+use utils::zipard;
 
+/// Stands in for the result of a subquery in a condition list, until it is decoded. It can't be
+/// part of a condition list written by the user.
+const SUBQUERY_MARKER: &str = "\u{1}subquery";
+
+/// A handle to a database. It can be shared between threads, cloning it returns another handle
+/// to the same tables. Storage settings and autosave are shared as well, only the job history and
+/// the split marker belong to each handle.
 #[derive(Clone, Debug)]
 pub struct Neith {
-    path: PathBuf,
-    ram_mode: bool,
-    autosave: bool,
+    // Pointing to the database, every clone of this handle shares it.
+    database: Arc<Database>,
     job_history: bool,
-    job_history_table_index: Option<usize>,
-    // putting tables on the heap, as they could grow quite large! Pointing to it also makes sense,
-    // along being needed for clone.
-    tables: Vec<Box<Rc<Mutex<Table>>>>,
     split_pattern: String,
 }
 
 impl Default for Neith {
 
     fn default() -> Self {
-        let tables: Vec<Box<Rc<Mutex<Table>>>> = Default::default();
-        let ram_mode = true;
-        let autosave = false;
+        let database = Arc::new(Database::default());
         let job_history = false;
-        let job_history_table_index = None;
-        let path = PathBuf::new();
         let split_pattern = ",+".to_string();
-        return Neith{ tables, path, ram_mode, autosave, job_history, job_history_table_index, split_pattern};
+        return Neith{ database, job_history, split_pattern};
     }
     
 }
@@ -54,19 +85,9 @@ impl Default for Neith {
 impl From<PathBuf> for Neith {
 
     fn from(value: PathBuf) -> Self {
-        let path = canonize_path(value);
-        let read_file = read_json_from_neithdb_file(path.clone());
-        let mut tables: Vec<Box<Rc<Mutex<Table>>>> = Default::default();
-        let ram_mode = false;
-        let autosave = false;
-        let job_history = false;
-        let job_history_table_index = None;
-        let split_pattern = ",+".to_string();
-        for table in read_file.entries() {
-            let table = Box::new(Rc::new(Mutex::new(Table::from(table))));
-            tables.push(table);
-        }
-        return Neith{ tables, path, ram_mode, autosave, job_history, job_history_table_index, split_pattern};
+        let mut out = Neith::new(value, false, false);
+        let _ = out.read_file().expect("Unable to read neithdb file!");
+        return out;
     }
 
 }
@@ -77,11 +98,9 @@ impl Neith {
     /// For general use, `connect(filename)` is highly recommended.
     pub fn new(value: PathBuf, ram_mode: bool, job_history: bool) -> Self {
         let path = canonize_path(value);
-        let tables: Vec<Box<Rc<Mutex<Table>>>> = Default::default();
-        let autosave = false;
-        let job_history_table_index = None;
+        let database = Arc::new(Database::new(Storage { path, ram_mode, ..Default::default() }));
         let split_pattern = ",+".to_string();
-        return Neith{ tables, path, ram_mode, autosave, job_history, job_history_table_index, split_pattern};
+        return Neith{ database, job_history, split_pattern};
     }
 
     /// Creates the connection to your database. Most if not all programs will start with this.
@@ -100,6 +119,46 @@ impl Neith {
         }
     }
 
+    /// Creates the connection to your database, saving it in the supplied format.
+    /// An existing database in the other format is read as usual, and converted during the
+    /// next save.
+    /// ```
+    /// use neith::{Neith, StorageFormat};
+    /// let con = Neith::connect_with_format("myDBname", StorageFormat::Binary);
+    /// ```
+    pub fn connect_with_format<P>(filename: P, format: StorageFormat) -> Self where P: AsRef<Path> + Clone, PathBuf: From<P> {
+        let connection = Neith::connect(filename);
+        if let Ok(mut storage) = connection.database.storage.write() {
+            storage.format = format;
+        }
+        return connection;
+    }
+
+    /// Creates the connection to a database encrypted with the supplied key. The file is
+    /// decrypted while connecting and encrypted again during every save. An existing database
+    /// that isn't encrypted is read as usual, and encrypted during the next save.
+    /// ```
+    /// use neith::Neith;
+    /// let con = Neith::connect_encrypted("myDBname", [7; 32]).unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    /// If the file couldn't be read, the key is wrong, or the file was changed since it was
+    /// encrypted.
+    #[cfg(feature = "encryption")]
+    pub fn connect_encrypted<P>(filename: P, key: [u8; 32]) -> Result<Self, Error> where P: AsRef<Path> + Clone, PathBuf: From<P> {
+        let mut connection = Neith::new(filename.into(), false, false);
+        let storage = connection.database.storage.write();
+        if storage.is_err() {
+            return Err(Error::other("Couldn't lock storage settings!"));
+        }
+        storage.unwrap().key = Some(cryptard::Key(key));
+        if check_for_persistant_db(connection.database.get_storage()?.path) {
+            let _ = connection.read_file()?;
+        }
+        return Ok(connection);
+    }
+
     /// Connect in ram mode. No way to save even if you want to!
     pub fn connect_ram_mode(job_history: bool) -> Self {
         let mut connection = Neith::default();
@@ -110,17 +169,15 @@ impl Neith {
     /// A toggle for job-history, set to true to record, set to false to not record.
     pub fn set_job_history(&mut self, value: bool) -> Result<Success, Error> {
         self.job_history = value;
-        if self.exists_table("job_history".to_string())? && self.job_history {
-            let index = self.search_for_table("job_history".to_string()).unwrap();
-            self.job_history_table_index = Some(index);
-            return Ok(Success::SuccessMessage(value));
-        } else if !self.exists_table("job_history".to_string())? && self.job_history {
+        if !self.exists_table("job_history".to_string())? && self.job_history {
             let table_columns: Vec<(String, bool)> = vec![("id".to_string(), true), ("command".to_string(), false), ("time".to_string(), false), ("duration".to_string(), false)];
             let table_prop = ("job_history".to_string(), table_columns);
-            let job_history_table = Box::new(Rc::new(Mutex::new(Table::from(table_prop))));
-            self.tables.push(job_history_table);
-            // Table len == number of elements. element 1 == index 0
-            self.job_history_table_index = Some(self.tables.len().saturating_sub(1));
+            let job_history_table = Arc::new(RwLock::new(Table::from(table_prop)));
+            match self.push_table(job_history_table) {
+                // Another handle created it in the meantime.
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => (),
+                answ => answ?,
+            }
         }
         return Ok(Success::SuccessMessage(value));
     }
@@ -131,8 +188,68 @@ impl Neith {
     }
 
     /// Saves the current state of the database to disc.
-    pub fn save(self) -> Result<Success, json::JsonError> {
-        return write_neithdb_file(self);
+    /// Every table is read while it is locked, nothing is copied.
+    pub fn save(&self) -> Result<Success, json::JsonError> {
+        return self.database.save();
+    }
+
+    /// Returns the format the database is saved in.
+    pub fn get_storage_format(&self) -> StorageFormat {
+        return self.database.get_storage().map(|storage| storage.format).unwrap_or_default();
+    }
+
+    /// Converts the database to the supplied format, and saves it right away. It is the format of
+    /// every handle to the database. In ram mode it is only used once made persistant.
+    ///
+    /// ## Errors
+    /// Will error if Neith does not have write permission at its path.
+    pub fn set_storage_format(&mut self, format: StorageFormat) -> Result<Success, json::JsonError> {
+        let ram_mode = self.change_storage(|storage| storage.format = format)?;
+        if ram_mode {
+            return Ok(Success::SuccessMessage(true));
+        }
+        return self.save();
+    }
+
+    /// Returns how the database is laid out on disc.
+    pub fn get_storage_layout(&self) -> StorageLayout {
+        return self.database.get_storage().map(|storage| storage.layout).unwrap_or_default();
+    }
+
+    /// Lays the database out on disc as supplied, and saves it right away. A `TablePerFile`
+    /// database is a directory at the path, only tables changed since the last save are written
+    /// again. It is the layout of every handle to the database. In ram mode it is only used once
+    /// made persistant.
+    ///
+    /// ## Errors
+    /// Will error if Neith does not have write permission at its path.
+    pub fn set_storage_layout(&mut self, layout: StorageLayout) -> Result<Success, json::JsonError> {
+        let ram_mode = self.change_storage(|storage| storage.layout = layout)?;
+        if ram_mode {
+            return Ok(Success::SuccessMessage(true));
+        }
+        return self.save();
+    }
+
+    /// Turns compression of the file on or off for every handle, and saves right away. A
+    /// compressed file is compressed again during every save. In ram mode it is only used once
+    /// made persistant.
+    ///
+    /// ## Errors
+    /// Will error if Neith does not have write permission at its path.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: bool) -> Result<Success, json::JsonError> {
+        let ram_mode = self.change_storage(|storage| storage.compression = compression)?;
+        if ram_mode {
+            return Ok(Success::SuccessMessage(true));
+        }
+        return self.save();
+    }
+
+    /// Returns if the file is compressed.
+    #[cfg(feature = "compression")]
+    pub fn get_compression(&self) -> bool {
+        return self.database.get_storage().is_ok_and(|storage| storage.compression);
     }
 
     /// Makes Neith persistant and saves it's current state at the supplied Path. This path needs
@@ -142,9 +259,11 @@ impl Neith {
     /// Will error if supplied with an incorrect path or if Neith does not have write permission at
     /// the supplied path.
     pub fn make_persistant(&mut self, save_path: PathBuf) -> Result<Success, json::JsonError> {
-        self.path = save_path;
-        self.ram_mode = false;
-        return self.clone().save();
+        let _ = self.change_storage(|storage| {
+            storage.path = save_path;
+            storage.ram_mode = false;
+        })?;
+        return self.save();
     }
 
     /// Sets the autosave feature to save after every interaction with Neith.
@@ -153,14 +272,148 @@ impl Neith {
     /// ## Errors
     /// Will error if ram mode is active.
     pub fn set_autosave(&mut self, save: bool) -> Result<Success, Error> {
-        if self.ram_mode {
+        if save {
+            return self.set_autosave_policy(AutosavePolicy::EveryStatement);
+        } else {
+            return self.set_autosave_policy(AutosavePolicy::Manual);
+        }
+    }
+
+    /// Sets when Neith saves on its own, see `AutosavePolicy`, for every handle. Unless it is
+    /// `Manual`, unsaved changes are also saved once the last handle to the database is dropped.
+    ///
+    /// ## Errors
+    /// Will error if ram mode is active.
+    pub fn set_autosave_policy(&mut self, policy: AutosavePolicy) -> Result<Success, Error> {
+        let storage = self.database.storage.write();
+        if storage.is_err() {
+            return Err(Error::other("Couldn't lock storage settings!"));
+        }
+        let mut ok_storage = storage.unwrap();
+        if ok_storage.ram_mode {
             return Err(Error::other("Ram mode active! No saving possible!"));
         } else {
-            self.autosave = save;
+            ok_storage.autosave = policy;
             return Ok(Success::SuccessMessage(true));
         }
     }
 
+    /// Changes the storage settings shared by every handle. Table files saved under the old
+    /// settings are forgotten while no save is running, the next save writes every table again.
+    ///
+    /// ## Returns
+    /// If ram mode is active.
+    ///
+    /// ## Errors
+    /// If the settings couldn't be locked.
+    fn change_storage<F>(&self, change: F) -> Result<bool, json::JsonError> where F: FnOnce(&mut Storage) {
+        let storage = self.database.storage.write();
+        if storage.is_err() {
+            return Err(json::JsonError::wrong_type("Couldn't lock storage settings!"));
+        }
+        let mut ok_storage = storage.unwrap();
+        change(&mut ok_storage);
+        if let Ok(mut saved_tables) = self.database.saved_tables.lock() {
+            saved_tables.clear();
+        }
+        return Ok(ok_storage.ram_mode);
+    }
+
+    /// Checks the database for damage, as `integrity check` does. If it is persistant, the
+    /// checksum of every table in its file is compared to the saved one first. Every table is then
+    /// checked for columns of different lengths, duplicates in unique columns, indexes out of step
+    /// with their column, rows violating a check constraint, entries json can't hold (if saved as
+    /// json) and foreign keys pointing to nothing.
+    ///
+    /// ## Returns
+    /// A `ResultSet` with a row for every check, in the columns `check`, `table`, `passed` and
+    /// `details`.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked.
+    pub fn verify(&self) -> Result<Success, Error> {
+        let mut checks: Vec<(String, String, bool, String)> = Vec::new();
+        let storage = self.database.get_storage()?;
+        if !storage.ram_mode && storage.path.exists() {
+            let verified = if storage.path.is_dir() {
+                dirard::verify_table_files(&storage)
+            } else {
+                read_neithdb_bytes(&storage, &storage.path).and_then(decompress_neithdb_bytes).and_then(|bytes| dirard::verify_neithdb_bytes(&bytes))
+            };
+            match verified {
+                Ok(tables) => {
+                    for (tablename, matches) in tables {
+                        match matches {
+                            Some(true) => checks.push(("checksum".to_string(), tablename, true, String::new())),
+                            Some(false) => checks.push(("checksum".to_string(), tablename, false, "the saved table does not match its checksum".to_string())),
+                            None => checks.push(("checksum".to_string(), tablename, true, "no checksum saved".to_string())),
+                        }
+                    }
+                },
+                Err(error) => checks.push(("checksum".to_string(), String::new(), false, error.to_string())),
+            }
+        }
+        let json = storage.format == StorageFormat::Json;
+        let mut references: Vec<(String, String, ForeignKey, Vec<Data>)> = Vec::new();
+        for entry in self.get_tables()? {
+            let table = entry.read();
+            if table.is_err() {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+            let ok_table = table.unwrap();
+            for (check, passed, details) in ok_table.verify(json) {
+                checks.push((check, ok_table.name.clone(), passed, details));
+            }
+            for column in &ok_table.columns {
+                if column.references.is_some() {
+                    let data: Vec<Data> = column.contents.all_row_data.iter().filter(|data| !data.is_null()).cloned().collect();
+                    references.push((ok_table.name.clone(), column.name.clone(), column.references.clone().unwrap(), data));
+                }
+            }
+        }
+        let mut broken: HashMap<String, Vec<String>> = HashMap::new();
+        for (tablename, columnname, reference, data) in references {
+            let missing = match self.find_table(&reference.table) {
+                Ok(table_handle) => {
+                    let table = table_handle.read();
+                    if table.is_err() {
+                        return Err(Error::other("Couldn't lock table!"));
+                    }
+                    let ok_table = table.unwrap();
+                    match ok_table.search_for_column(reference.column.clone()) {
+                        Ok(column_index) => data.iter().filter(|entry| !ok_table.columns[column_index].contents.all_row_data.contains(entry)).count(),
+                        Err(_) => data.len(),
+                    }
+                },
+                Err(_) => data.len(),
+            };
+            if missing > 0 {
+                broken.entry(tablename).or_default().push(format!("{} entries of {} point to nothing in {}({})", missing, columnname, reference.table, reference.column));
+            }
+        }
+        let tablenames: Vec<String> = checks.iter().filter(|check| check.0 == "column lengths").map(|check| check.1.clone()).collect();
+        for tablename in tablenames {
+            let details = broken.remove(&tablename).unwrap_or_default();
+            checks.push(("foreign keys".to_string(), tablename, details.is_empty(), details.join(", ")));
+        }
+        let mut check_column: Vec<Data> = Vec::new();
+        let mut table_column: Vec<Data> = Vec::new();
+        let mut passed_column: Vec<Data> = Vec::new();
+        let mut details_column: Vec<Data> = Vec::new();
+        for (check, tablename, passed, details) in checks {
+            check_column.push(Data::String(check));
+            table_column.push(Data::String(tablename));
+            passed_column.push(Data::Bool(passed));
+            details_column.push(Data::String(details));
+        }
+        return Ok(Success::ResultSet(ResultSet::from(vec![
+            ("check".to_string(), check_column),
+            ("table".to_string(), table_column),
+            ("passed".to_string(), passed_column),
+            ("details".to_string(), details_column),
+        ])));
+    }
+
     // This is the general apperance of a mk_table call.
     // mk_table(table_name, column_vec((column_name0, unique_bool, type)), (column_name1, unique_bool, type))
     //
@@ -198,7 +451,80 @@ impl Neith {
     /// ## Errors
     /// This function has many different ways to error. Please read the error message carefully, as
     /// it contains important information in most cases.
-    pub fn execute(&mut self, query: &str) -> Result<Success, io::Error> {
+    pub fn execute(&self, query: &str) -> Result<Success, io::Error> {
+        let answ = self.execute_query(query)?;
+        // Saving after the query is done, no table is locked anymore.
+        if Neith::changes_data(query) {
+            self.autosave_statement();
+        }
+        return Ok(answ);
+    }
+
+    /// Checks if the statement can change data, statements only reading it don't count for
+    /// autosave.
+    fn changes_data(query: &str) -> bool {
+        let command = strip_leading_word(query.to_string()).0;
+        return !matches!(command.as_str(), "select" | "get" | "show" | "describe" | "explain" | "integrity");
+    }
+
+    /// Writes many rows of new data into a table at once, just like
+    /// `new data 'tablename' (...), (...), ...` does. Every row is a list of column names along
+    /// with their data, columns left out are `Null`.
+    /// Every row is checked before any is kept, and it counts as a single statement for autosave
+    /// and the job history.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the table or a column doesn't exist, or any row violates a constraint. Then no row is
+    /// written.
+    pub fn insert_many(&self, tablename: &str, rows: Vec<Vec<(String, Data)>>) -> Result<Success, Error> {
+        let start = Instant::now();
+        let date = chrono::Utc::now().to_rfc3339();
+        let row_count = rows.len();
+        let answ = self.insert_rows(tablename.to_string(), rows, None)?;
+        if self.job_history {
+            let history_table_handle = self.find_table("job_history")?;
+            let history_table = Neith::write_table(&history_table_handle);
+            if history_table.is_ok() {
+                let _ = self.write_history(format!("insert many {} rows into {}", row_count, tablename), date, start, history_table.unwrap())?;
+            }
+        }
+        self.autosave_statement();
+        return Ok(answ);
+    }
+
+    /// Counts a statement changing data for autosave, and saves if the policy says so.
+    fn autosave_statement(&self) {
+        let storage = self.database.get_storage();
+        if storage.as_ref().is_ok_and(|storage| !storage.ram_mode) {
+            let save = match self.database.autosave_state.lock() {
+                Ok(mut state) => state.count_statement(&storage.unwrap().autosave),
+                Err(_) => false,
+            };
+            if save {
+                let _ = self.save();
+            }
+        }
+    }
+
+    /// Writes every row of new data into the table, or none if any of them violates a
+    /// constraint or foreign key.
+    ///
+    /// ## Errors
+    /// If the table or a column doesn't exist, or a row violates a constraint or foreign key.
+    fn insert_rows(&self, tablename: String, rows: Vec<Vec<(String, Data)>>, on_conflict: Option<(String, OnConflict)>) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        let referenced = self.referenced_tables(&table_handle)?;
+        let (mut table, referenced_tables) = self.lock_in_order(&table_handle, &referenced)?;
+        Neith::check_references(&table, &referenced_tables, &rows.concat())?;
+        let answ = table.new_data_rows(rows, on_conflict);
+        return Neith::count_change(&mut table, answ);
+    }
+
+    /// Decodes and executes the query, everything `execute` does but saving.
+    fn execute_query(&self, query: &str) -> Result<Success, io::Error> {
         // Conditional variables for job_history
         let start = Instant::now();
         let date = chrono::Utc::now().to_rfc3339();
@@ -213,28 +539,53 @@ impl Neith {
                 match command_lvl2.0.as_str() {
                     "table" => {
                         let command_lvl4 = strip_leading_word(command_lvl3.1);
-                        if command_lvl4.0.as_str().contains("with") {
-                            if self.search_for_table(tablename.clone()).is_ok() {
+                        if command_lvl4.0.as_str() == "as" {
+                            let command_lvl5 = strip_leading_word(command_lvl4.1);
+                            if command_lvl5.0.as_str() != "select" {
+                                return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'select'", command_lvl5.0)));
+                            }
+                            let answ = self.new_table_as_select(tablename, command_lvl5.1)?;
+                            if self.job_history {
+                                let history_table_handle = self.find_table("job_history")?;
+                                let history_table = Neith::write_table(&history_table_handle);
+                                if history_table.is_ok() {
+                                    let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                                }
+                            }
+                            return Ok(answ);
+                        } else if command_lvl4.0.as_str().contains("with") {
+                            if self.exists_table(tablename.clone())? {
                                 // Table exists already; Don't do anything act like everything is
                                 // fine!
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
-                                }
                                 return Ok(Success::SuccessMessage(true));
                             }
-                            let columns = decode_columnmaker(command_lvl4.1).unwrap();
-                            let answ = Box::new(Rc::new(Mutex::new(Table::from((tablename, columns)))));
-                            self.tables.push(answ);
+                            let column_list = strip_enclosed_list(command_lvl4.1, '(', ')')?;
+                            let columns = decode_columnmaker(column_list.0)?;
+                            self.validate_references(&tablename, &columns)?;
+                            let checks = decode_checks(column_list.1, &tablename, self.split_pattern.clone())?;
+                            let mut new_table = Table::from((tablename, columns));
+                            for check in &checks {
+                                for condition in &check.conditions {
+                                    if let Condition::Compare(columnname, _, _) = condition {
+                                        let _ = new_table.search_for_column(columnname.to_string())?;
+                                    }
+                                }
+                            }
+                            new_table.checks = checks;
+                            let answ = Arc::new(RwLock::new(new_table));
+                            match self.push_table(answ) {
+                                // Created by another handle in the meantime, just the same.
+                                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => return Ok(Success::SuccessMessage(true)),
+                                answ => answ?,
+                            }
                             if self.job_history {
-                                let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
+                                let history_table_handle = self.find_table("job_history")?;
+                                let history_table = Neith::write_table(&history_table_handle);
                                 if history_table.is_ok() {
                                     let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                 }
                             }
                             // Successful decoding of syntax!
-                            if self.autosave && !self.ram_mode {
-                                let _ = self.clone().save();
-                            }
                             return Ok(Success::SuccessMessage(true));
                         } else {
                             return Err(Error::other("Invalid nql syntax."));
@@ -243,61 +594,59 @@ impl Neith {
                     "column" => {
                         let command_lvl4 = strip_leading_word(command_lvl3.1);
                         if command_lvl4.0.as_str().contains("with") {
-                            let columns = decode_columnmaker(command_lvl4.1).unwrap();
-                            let table_index = self.search_for_table(tablename)?;
-                            let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let mut ok_table = table.unwrap();
-                                let answ = ok_table.new_columns(columns);
-                                // Successful decoding of syntax!
-                                if answ == Success::SuccessMessage(true) {
-                                    if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                        if history_table.is_ok() {
-                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                        }
-                                    }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
+                            let columns = decode_columnmaker(command_lvl4.1)?;
+                            self.validate_references(&tablename, &columns)?;
+                            let table_handle = self.find_table(&tablename)?;
+                            // A foreign key can't appear while a delete collects the tables pointing to
+                            // the one it deletes from.
+                            let all_tables = if columns.iter().any(|column| column.references.is_some()) {
+                                match self.database.tables.write() {
+                                    Ok(all_tables) => Some(all_tables),
+                                    Err(_) => return Err(Error::other("Couldn't lock tables!")),
+                                }
+                            } else {
+                                None
+                            };
+                            // The table is unlocked again before the job history is written.
+                            let answ = match Neith::write_table(&table_handle) {
+                                Ok(mut table) => {
+                                    let answ = table.new_columns(columns);
+                                    table.changes += 1;
+                                    self.database.update_foreign_keys(&table);
+                                    answ
+                                },
+                                Err(_) => return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!")),
+                            };
+                            drop(all_tables);
+                            // Successful decoding of syntax!
+                            if answ == Success::SuccessMessage(true) {
+                                if self.job_history {
+                                    let history_table_handle = self.find_table("job_history")?;
+                                    let history_table = Neith::write_table(&history_table_handle);
+                                    if history_table.is_ok() {
+                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                     }
-                                    return Ok(answ);
-                                } else {
-                                    return Err(Error::other("Invalid nql syntax."));
                                 }
+                                return Ok(answ);
                             } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+                                return Err(Error::other("Invalid nql syntax."));
                             }
                         } else {
                             return Err(Error::other("Invalid nql syntax."));
                         }
                     },
                     "data" => {
-                        let command_lvl4 = command_lvl3.1;
-                        let decoded = decode_list_columndata(command_lvl4, self.split_pattern.clone());
-                        let table_index = self.search_for_table(tablename)?;
-                        let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let mut ok_table = table.unwrap();
-                                let answ = ok_table.new_data(decoded)?;
-                                // Successful decoding of syntax!
-                                if answ == Success::SuccessMessage(true) {
-                                    if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                        if history_table.is_ok() {
-                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                        }
-                                    }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
-                                    }
-                                    return Ok(answ);
-                                } else {
-                                    return Err(Error::other("Invalid nql syntax."));
-                                }
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+                        let (rows, on_conflict) = decode_new_rows(command_lvl3.1, self.split_pattern.clone())?;
+                        let answ = self.insert_rows(tablename, rows, on_conflict)?;
+                        // Successful decoding of syntax!
+                        if self.job_history {
+                            let history_table_handle = self.find_table("job_history")?;
+                            let history_table = Neith::write_table(&history_table_handle);
+                            if history_table.is_ok() {
+                                let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                             }
-
+                        }
+                        return Ok(answ);
                     },
                     _ => return Err(Error::other("Invalid nql syntax.")),
                 }
@@ -312,14 +661,12 @@ impl Neith {
                             let answ = self.delete_table(tablename);
                             if answ.is_ok() {
                                 if self.job_history {
-                                    let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
+                                    let history_table_handle = self.find_table("job_history")?;
+                                    let history_table = Neith::write_table(&history_table_handle);
                                     if history_table.is_ok() {
                                         let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                     }
                                 }
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
-                                }
                                 return Ok(answ.unwrap());
                             } else {
                                 return Err(Error::other("Invalid nql syntax."));
@@ -339,14 +686,12 @@ impl Neith {
                                 let answ = self.delete_column(tablename, columnname);
                                 if answ.is_ok() {
                                     if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
+                                        let history_table_handle = self.find_table("job_history")?;
+                                        let history_table = Neith::write_table(&history_table_handle);
                                         if history_table.is_ok() {
                                             let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                         }
                                     }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
-                                    }
                                     return Ok(answ.unwrap());
                                 } else {
                                     return Err(Error::other("Invalid nql syntax."));
@@ -363,32 +708,23 @@ impl Neith {
                         if command_lvl3.0.as_str().contains("in") {
                             let command_lvl4 = strip_leading_word(command_lvl3.1);
                             let tablename = command_lvl4.0;
-                            let table_index = self.search_for_table(tablename)?;
+                            let table_handle = self.find_table(&tablename)?;
                             let command_lvl5 = strip_leading_word(command_lvl4.1);
                             if command_lvl5.0.as_str().contains("where"){
-                                let conditions = command_lvl5.1;
-                                let finished_search = self.search_conditionals(conditions, table_index)?;
-                                let table = self.tables[table_index].lock();
-                                if table.is_ok() {
-                                    let mut ok_table = table.unwrap();
-                                    let answ = ok_table.delete_data(finished_search)?;
-                                    // Successful decoding of syntax!
-                                    if answ == Success::SuccessMessage(true) {
-                                        if self.job_history {
-                                            let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                            if history_table.is_ok() {
-                                                let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                            }
-                                        }
-                                        if self.autosave && !self.ram_mode {
-                                            let _ = self.clone().save();
+                                let conditions = self.decode_conditions(command_lvl5.1)?;
+                                let answ = self.delete_rows(&table_handle, |table| table.search_conditions(&conditions))?;
+                                // Successful decoding of syntax!
+                                if answ == Success::SuccessMessage(true) {
+                                    if self.job_history {
+                                        let history_table_handle = self.find_table("job_history")?;
+                                        let history_table = Neith::write_table(&history_table_handle);
+                                        if history_table.is_ok() {
+                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                         }
-                                        return Ok(answ);
-                                    } else {
-                                        return Err(Error::other("Invalid nql syntax."));
                                     }
+                                    return Ok(answ);
                                 } else {
-                                    return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+                                    return Err(Error::other("Invalid nql syntax."));
                                 }
                             } else {
                                 return Err(Error::other("Invalid nql syntax."));
@@ -409,31 +745,36 @@ impl Neith {
                     let conditions = command_lvl4.0;
                     let command_lvl5 = strip_leading_word(command_lvl4.1);
                     if command_lvl5.0.as_str().contains("with") {
-                        let decoded_list = decode_list_columndata(command_lvl5.1, self.split_pattern.clone());
-                        let table_index = self.search_for_table(tablename)?;
-                        let search = self.search_conditionals(conditions, table_index)?;
-                        let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let mut ok_table = table.unwrap();
-                                let answ = ok_table.update_data(decoded_list, search)?;
-                                // Successful decoding of syntax!
-                                if answ == Success::SuccessMessage(true) {
-                                    if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                        if history_table.is_ok() {
-                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                        }
-                                    }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
-                                    }
-                                    return Ok(answ);
-                                } else {
-                                    return Err(Error::other("Invalid nql syntax."));
+                        let table_handle = self.find_table(&tablename)?;
+                        // Subqueries are executed before the table is locked.
+                        let encoded_conditions = self.decode_conditions(conditions)?;
+                        let referenced = self.referenced_tables(&table_handle)?;
+                        // Rows are found, computed and updated while the table is locked once, no
+                        // other statement can change them in between. It is unlocked again before
+                        // the job history is written.
+                        let answ = {
+                            let (mut table, referenced_tables) = self.lock_in_order(&table_handle, &referenced)?;
+                            let search = table.search_conditions(&encoded_conditions)?;
+                            let columnnames: Vec<String> = table.columns.iter().map(|column| column.name.clone()).collect();
+                            let decoded_list = decode_assignments(decode_list_columndata(command_lvl5.1, self.split_pattern.clone()), &columnnames);
+                            let computed_list = table.compute_updates(&decoded_list, &search)?;
+                            Neith::check_references(&table, &referenced_tables, &distinct_entries(&computed_list))?;
+                            let answ = table.update_each_row(computed_list, search);
+                            Neith::count_change(&mut table, answ)?
+                        };
+                        // Successful decoding of syntax!
+                        if answ == Success::SuccessMessage(true) {
+                            if self.job_history {
+                                let history_table_handle = self.find_table("job_history")?;
+                                let history_table = Neith::write_table(&history_table_handle);
+                                if history_table.is_ok() {
+                                    let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                 }
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
                             }
+                            return Ok(answ);
+                        } else {
+                            return Err(Error::other("Invalid nql syntax."));
+                        }
                     } else {
                         return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'where'.", command_lvl3.0)));
                     }
@@ -442,88 +783,16 @@ impl Neith {
                 }
             },
             "select" => {
-                let command_lvl2 = strip_column_list(command_lvl1.1)?;
-                let command_lvl3 = strip_leading_word(command_lvl2.1);
-                if command_lvl3.0.as_str().contains("from") {
-                    let command_lvl4 = strip_leading_word(command_lvl3.1);
-                    let tablename = command_lvl4.0;
-                    let table_index = self.search_for_table(tablename)?;
-                    let new_tmp_table = {
-                        let tmp = self.tables[table_index].lock();
-                        if tmp.is_ok() {
-                            tmp.unwrap().clone()
-                        } else {
-                            return Err(Error::other("Couldn't lock table!"));
-                        }
-                    };
-                    let decoded_column_list: Vec<String> = decode_column_list(command_lvl2.0.clone(), new_tmp_table);
-                    let command_lvl5 = strip_leading_word(command_lvl4.1);
-                    if command_lvl2.0.as_str().contains("*") && binding.split_whitespace().count() == 4 {
-                        let search = self.select_all_rows(table_index)?;
-                        let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let ok_table = table.unwrap();
-                                let answ = ok_table.select_data(decoded_column_list, search);
-                                // Successful decoding of syntax!
-                                if self.job_history {
-                                    let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                    if history_table.is_ok() {
-                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                    }
-                                }
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
-                                }
-                                return Ok(answ); 
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
-                            }
-                    } else if !binding.contains("where") {
-                        let search = self.select_all_rows(table_index)?;
-                        let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let ok_table = table.unwrap();
-                                let answ = ok_table.select_data(decoded_column_list, search);
-                                if self.job_history {
-                                    let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                    if history_table.is_ok() {
-                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                    }
-                                }
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
-                                }
-                                return Ok(answ);
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
-                            }
-                    } else if command_lvl5.0.as_str().contains("where") {
-                        let conditions = command_lvl5.1;
-                        let search = self.search_conditionals(conditions.clone(), table_index)?;
-                        let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let ok_table = table.unwrap();
-                                let answ = ok_table.select_data(decoded_column_list, search);
-                                // Successful decoding of syntax!
-                                if self.job_history {
-                                    let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                    if history_table.is_ok() {
-                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                    }
-                                }
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
-                                }
-                                return Ok(answ);                           
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
-                            }
-                    } else {
-                        return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'where'", command_lvl5.1)));
+                let answ = self.select(command_lvl1.1)?;
+                // Successful decoding of syntax!
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                     }
-                } else {
-                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'from'", command_lvl3.1)));
                 }
+                return Ok(answ);
             },
             "get" => {
                 let command_lvl2 = strip_leading_word(command_lvl1.1);
@@ -536,26 +805,24 @@ impl Neith {
                             let command_lvl5 = strip_leading_word(command_lvl4.1);
                             if command_lvl5.0.as_str().contains("from") {
                                 let tablename = command_lvl5.1;
-                                let table_index = self.search_for_table(tablename)?;
-                                let table = self.tables[table_index].lock();
-                                if table.is_ok() {
-                                    let ok_table = table.unwrap();
-                                    let column_index = ok_table.search_for_column(columnname)?;
-                                    let answ = ok_table.columns[column_index].min();
-                                    // Successful decoding of syntax!
-                                    if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                        if history_table.is_ok() {
-                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                        }
-                                    }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
+                                let table_handle = self.find_table(&tablename)?;
+                                // The table is unlocked again before the job history is written.
+                                let answ = match table_handle.read() {
+                                    Ok(table) => {
+                                        let column_index = table.search_for_column(columnname)?;
+                                        table.columns[column_index].min()
+                                    },
+                                    Err(_) => return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!")),
+                                };
+                                // Successful decoding of syntax!
+                                if self.job_history {
+                                    let history_table_handle = self.find_table("job_history")?;
+                                    let history_table = Neith::write_table(&history_table_handle);
+                                    if history_table.is_ok() {
+                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                     }
-                                    return Ok(answ);
-                                }  else {
-                                    return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
                                 }
+                                return Ok(answ);
                             } else {
                                 return Err(Error::other(format!("Invalid nql syntax. {:?} should be one 'from'", command_lvl5.0)));
                             }
@@ -572,26 +839,24 @@ impl Neith {
                             let command_lvl5 = strip_leading_word(command_lvl4.1);
                             if command_lvl5.0.as_str().contains("from") {
                                 let tablename = command_lvl5.1;
-                                let table_index = self.search_for_table(tablename)?;
-                                let table = self.tables[table_index].lock();
-                                if table.is_ok() {
-                                    let ok_table = table.unwrap();
-                                    let column_index = ok_table.search_for_column(columnname)?;
-                                    let answ = ok_table.columns[column_index].max();
-                                    // Successful decoding of syntax!
-                                    if self.job_history {
-                                        let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                        if history_table.is_ok() {
-                                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                        }
-                                    }
-                                    if self.autosave && !self.ram_mode {
-                                        let _ = self.clone().save();
+                                let table_handle = self.find_table(&tablename)?;
+                                // The table is unlocked again before the job history is written.
+                                let answ = match table_handle.read() {
+                                    Ok(table) => {
+                                        let column_index = table.search_for_column(columnname)?;
+                                        table.columns[column_index].max()
+                                    },
+                                    Err(_) => return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!")),
+                                };
+                                // Successful decoding of syntax!
+                                if self.job_history {
+                                    let history_table_handle = self.find_table("job_history")?;
+                                    let history_table = Neith::write_table(&history_table_handle);
+                                    if history_table.is_ok() {
+                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                     }
-                                    return Ok(answ);
-                                } else {
-                                    return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
                                 }
+                                return Ok(answ);
                             } else {
                                 return Err(Error::other(format!("Invalid nql syntax. {:?} should be one 'from'", command_lvl5.0)));
                             }
@@ -604,24 +869,20 @@ impl Neith {
                         if command_lvl3.0.contains("of") {
                             let command_lvl4 = strip_leading_word(command_lvl3.1);
                             let tablename = command_lvl4.0;
-                            let table_index = self.search_for_table(tablename)?;
-                            let table = self.tables[table_index].lock();
-                            if table.is_ok() {
-                                let ok_table = table.unwrap();
-                                let answ = ok_table.len();
-                                if self.job_history {
-                                    let history_table = self.tables[self.job_history_table_index.unwrap()].lock();
-                                    if history_table.is_ok() {
-                                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
-                                    }
-                                }
-                                if self.autosave && !self.ram_mode {
-                                    let _ = self.clone().save();
+                            let table_handle = self.find_table(&tablename)?;
+                            // The table is unlocked again before the job history is written.
+                            let answ = match table_handle.read() {
+                                Ok(table) => table.len(),
+                                Err(_) => return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!")),
+                            };
+                            if self.job_history {
+                                let history_table_handle = self.find_table("job_history")?;
+                                let history_table = Neith::write_table(&history_table_handle);
+                                if history_table.is_ok() {
+                                    let _ = self.write_history(binding, date, start, history_table.unwrap())?;
                                 }
-                                return Ok(Success::Result(vec![Data::from(answ.to_string(), self.split_pattern.clone())]));
-                            } else {
-                                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
                             }
+                            return Ok(Success::Result(vec![Data::from(answ.to_string(), self.split_pattern.clone())]));
                         } else {
                             return Err(Error::other(format!("Invalid nql syntax. {:?} should be one 'of'", command_lvl3.0)));
                         }
@@ -631,14 +892,149 @@ impl Neith {
                         },
                     }
                 },
-            _ => { 
-                println!("ERROR: {:?} | {:?} | {:?}", query, command_lvl1.0, command_lvl1.1);
-                return Err(Error::other("Invalid nql syntax."));
+            "explain" => {
+                let command_lvl2 = strip_leading_word(command_lvl1.1);
+                if command_lvl2.0.as_str() != "select" {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'select'", command_lvl2.0)));
+                }
+                let answ = self.explain_select(command_lvl2.1)?;
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            "integrity" => {
+                let command_lvl2 = strip_leading_word(command_lvl1.1);
+                if command_lvl2.0.as_str() != "check" || !command_lvl2.1.trim().is_empty() {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'check'", command_lvl2.0)));
+                }
+                let answ = self.verify()?;
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            "create" => {
+                let command_lvl2 = strip_leading_word(command_lvl1.1);
+                let command_lvl3 = strip_leading_word(command_lvl2.1);
+                if command_lvl2.0.as_str() == "index" && command_lvl3.0.as_str() == "on" {
+                    let command_lvl4 = strip_leading_word(command_lvl3.1);
+                    let ordered = match command_lvl4.1.trim() {
+                        "" => false,
+                        "ordered" => true,
+                        _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'ordered' or nothing", command_lvl4.1))),
+                    };
+                    let (tablename, columnname) = decode_table_column(&command_lvl4.0)?;
+                    let table_handle = self.find_table(&tablename)?;
+                    // The table is unlocked again before the job history is written.
+                    let answ = match Neith::write_table(&table_handle) {
+                        Ok(mut table) => {
+                            let answ = table.create_index(columnname, ordered);
+                            Neith::count_change(&mut table, answ)?
+                        },
+                        Err(_) => return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!")),
+                    };
+                    if self.job_history {
+                        let history_table_handle = self.find_table("job_history")?;
+                        let history_table = Neith::write_table(&history_table_handle);
+                        if history_table.is_ok() {
+                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                        }
+                    }
+                    return Ok(answ);
+                } else {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'index on'", command_lvl2.0)));
+                }
+            },
+            "show" => {
+                if command_lvl1.1.trim() != "tables" {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'tables'", command_lvl1.1)));
+                }
+                let answ = show_tables(&self.schema()?);
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            "describe" => {
+                let table_handle = self.find_table(command_lvl1.1.trim())?;
+                let table = table_handle.read();
+                if table.is_ok() {
+                    let answ = TableSchema::from(&*table.unwrap()).describe();
+                    if self.job_history {
+                        let history_table_handle = self.find_table("job_history")?;
+                        let history_table = Neith::write_table(&history_table_handle);
+                        if history_table.is_ok() {
+                            let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                        }
+                    }
+                    return Ok(answ);
+                } else {
+                    return Err(Error::other("Couldn't lock table!"));
+                }
+            },
+            "truncate" => {
+                let tablename = command_lvl1.1.trim().to_string();
+                if tablename.is_empty() || tablename.contains(char::is_whitespace) {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'tablename'", command_lvl1.1)));
+                }
+                let table_handle = self.find_table(&tablename)?;
+                let answ = self.delete_rows(&table_handle, |table| Ok(table.select_all_rows()))?;
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            "copy" => {
+                let words: Vec<&str> = command_lvl1.1.split_whitespace().collect();
+                let answ = match words.as_slice() {
+                    ["table", tablename, "to", new_name] => self.copy_table(tablename.to_string(), new_name.to_string())?,
+                    _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'table 'tablename' to 'new_name''", command_lvl1.1))),
+                };
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            "alter" => {
+                let answ = self.alter(command_lvl1.1)?;
+                if self.job_history {
+                    let history_table_handle = self.find_table("job_history")?;
+                    let history_table = Neith::write_table(&history_table_handle);
+                    if history_table.is_ok() {
+                        let _ = self.write_history(binding, date, start, history_table.unwrap())?;
+                    }
+                }
+                return Ok(answ);
+            },
+            _ => { 
+                println!("ERROR: {:?} | {:?} | {:?}", query, command_lvl1.0, command_lvl1.1);
+                return Err(Error::other("Invalid nql syntax."));
             },
         }
     }
 
-    fn write_history(&self, binding: String, date: String, start: Instant, table: MutexGuard<Table>) -> Result<(), Error> {
+    fn write_history(&self, binding: String, date: String, start: Instant, table: RwLockWriteGuard<Table>) -> Result<(), Error> {
         // I use length => no need to add +1, len does that by
         // itself.
         let mut ok_table = table;
@@ -646,15 +1042,16 @@ impl Neith {
         let duration = start.elapsed().as_micros().to_string();
         let sp = &self.split_pattern;
         let decoded = decode_list_columndata(format!("(id = {id}{sp} command = {binding}{sp} time = {date}{sp} duration = {duration})"), self.split_pattern.clone());
-        let _ = ok_table.new_data(decoded);
+        let answ = ok_table.new_data(decoded);
+        let _ = Neith::count_change(&mut ok_table, answ);
         return Ok(());
         
     }
 
     /// Check if a table exists. returns `true` if it is found, `false` otherwise.
     pub fn exists_table(&self, name: String) -> Result<bool, Error> {
-        for table in &self.tables {
-            let temp = table.lock();
+        for table in self.get_tables()? {
+            let temp = table.read();
             if temp.is_ok() {
                 let ok_store = temp.unwrap();
                 if ok_store.name == name {
@@ -667,140 +1064,983 @@ impl Neith {
         return Ok(false);
     }
 
-    fn select_all_rows(&self, table_index: usize) -> Result<Vec<usize>, Error> {
-        let table = self.tables[table_index].lock();
-        if table.is_ok() {
+    /// Describes every table of the database, in the order they were created.
+    ///
+    /// ## Returns
+    /// A `TableSchema` for each table, with its row count and the name, uniqueness, index and
+    /// foreign key of each column, along with the names of its check constraints.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked.
+    pub fn schema(&self) -> Result<Vec<TableSchema>, Error> {
+        let mut out: Vec<TableSchema> = Vec::new();
+        for table in self.get_tables()? {
+            let temp = table.read();
+            if temp.is_ok() {
+                out.push(TableSchema::from(&*temp.unwrap()));
+            } else {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+        }
+        return Ok(out);
+    }
+
+    /// Decodes a condition list, executing every subquery in it first. A subquery is a select
+    /// statement in parenthesis, following `in` or `exists`:
+    /// `['columnname' in (select ...),+ and exists (select ...)]`
+    /// Neither may use a table the caller has locked.
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql, a subquery errors, or a subquery following `in` doesn't
+    /// select exactly one column.
+    fn decode_conditions(&self, conditions: String) -> Result<Vec<Condition>, Error> {
+        if conditions.contains(SUBQUERY_MARKER) {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} contains a reserved character.", conditions)));
+        }
+        let mut resolved = String::new();
+        let mut results: Vec<Data> = Vec::new();
+        let mut remainder = conditions;
+        while let Some(position) = find_subquery(&remainder) {
+            let before = remainder[..position].trim_end();
+            let keyword = before.rsplit(|character: char| character.is_whitespace() || character == '[').next().unwrap_or_default();
+            let (subquery, after) = strip_enclosed_list(remainder[position..].to_string(), '(', ')')?;
+            let columns = match self.select(subquery[8..subquery.len() - 1].to_string()) {
+                Ok(result) => result.get_result().unwrap_or_default(),
+                // A subquery finding no data is an empty result, not an error.
+                Err(error) if error.kind() == io::ErrorKind::NotFound => vec![Data::List(Vec::new())],
+                Err(error) => return Err(error),
+            };
+            if keyword == "exists" {
+                let exists = columns.first().and_then(|column| column.get_list()).is_some_and(|column| !column.is_empty());
+                resolved.push_str(&format!("{} {} ", before, exists));
+            } else if keyword == "in" {
+                if columns.len() != 1 {
+                    return Err(Error::other(format!("Invalid nql syntax. The subquery {:?} has to select exactly one column.", subquery)));
+                }
+                // The result can't be written as nql, it is put in after decoding.
+                resolved.push_str(&format!("{} {}{} ", before, SUBQUERY_MARKER, results.len()));
+                results.push(columns[0].clone());
+            } else {
+                return Err(Error::other(format!("Invalid nql syntax. The subquery {:?} should follow 'in' or 'exists'.", subquery)));
+            }
+            remainder = after;
+        }
+        resolved.push_str(&remainder);
+        let decoded_conditions = decode_list_conditions(resolved, self.split_pattern.clone())?;
+        let mut encoded_conditions = encode_list_conditions(decoded_conditions, self.split_pattern.clone())?;
+        for condition in encoded_conditions.iter_mut() {
+            if let Condition::Compare(_, _, data) = condition {
+                let position = data.get_string().and_then(|value| value.strip_prefix(SUBQUERY_MARKER).and_then(|position| position.parse::<usize>().ok()));
+                if let Some(position) = position {
+                    let result = results.get(position);
+                    if result.is_none() {
+                        return Err(Error::other(format!("Subquery {} of the condition list not found.", position)));
+                    }
+                    // No entry is in an empty result.
+                    if result.unwrap().get_list().is_some_and(|list| list.is_empty()) {
+                        *condition = Condition::Exists(false);
+                    } else {
+                        *data = result.unwrap().clone();
+                    }
+                }
+            }
+        }
+        return Ok(encoded_conditions);
+    }
+
+    /// Decodes and executes the remainder of a select statement, the rows of a single table or
+    /// of tables joined together. A leading `distinct` removes every duplicate row of the result.
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql, or a table or column doesn't exist.
+    fn select(&self, query: String) -> Result<Success, Error> {
+        let command_lvl1 = strip_leading_word(query.clone());
+        if command_lvl1.0.as_str() == "distinct" {
+            return Ok(self.select(command_lvl1.1)?.distinct());
+        }
+        let command_lvl2 = strip_column_list(query.clone())?;
+        let command_lvl3 = strip_leading_word(command_lvl2.1);
+        if command_lvl3.0.as_str().contains("from") {
+            let command_lvl4 = strip_leading_word(command_lvl3.1);
+            if starts_with_join(&command_lvl4.1) {
+                return self.select_join(command_lvl2.0, command_lvl4.0, command_lvl4.1);
+            }
+            let tablename = command_lvl4.0;
+            let table_handle = self.find_table(&tablename)?;
+            let command_lvl5 = strip_leading_word(command_lvl4.1.clone());
+            let (conditions, order) = if command_lvl2.0.as_str().contains("*") && query.split_whitespace().count() == 3 {
+                (None, None)
+            } else if !query.contains("where") {
+                (None, strip_order_by(command_lvl4.1)?.1)
+            } else if command_lvl5.0.as_str().contains("where") {
+                let (conditions, order) = strip_order_by(command_lvl5.1)?;
+                // Subqueries are executed before the table is locked.
+                (Some(self.decode_conditions(conditions)?), order)
+            } else {
+                return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'where'", command_lvl5.1)));
+            };
+            // Everything is read while the table is locked once.
+            let table = table_handle.read();
+            if table.is_err() {
+                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+            }
             let ok_table = table.unwrap();
-            let out = ok_table.select_all_rows();
-            return Ok(out.to_vec());
+            let decoded_column_list: Vec<String> = decode_column_list(command_lvl2.0.clone(), ok_table.clone())?;
+            if command_lvl2.0.as_str().contains("*") && query.split_whitespace().count() == 3 {
+                let search = ok_table.select_all_rows();
+                return Ok(ok_table.select_data(decoded_column_list, search));
+            }
+            let search = match conditions {
+                Some(conditions) => ok_table.search_conditions(&conditions)?,
+                None => ok_table.select_all_rows(),
+            };
+            let search = match order {
+                Some((columnname, descending)) => ok_table.order_rows(search, columnname, descending)?,
+                None => search,
+            };
+            return ok_table.select_entries(decoded_column_list, search);
+        } else {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'from'", command_lvl3.1)));
+        }
+    }
+
+    /// Selects the rows of tables joined together, the remainder of a select statement following
+    /// the name of the first table: `join 'tablename' on 'a.column' = 'b.column' where [...]`
+    /// Every table is read at the same time.
+    ///
+    /// ## Returns
+    /// A `ResultSet` naming every column as `tablename.columnname`.
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql, a table or column doesn't exist, or a table is joined more
+    /// than once.
+    fn select_join(&self, column_list: String, tablename: String, remainder: String) -> Result<Success, Error> {
+        let (joins, remainder) = decode_joins(remainder)?;
+        let mut table_handles: Vec<Arc<RwLock<Table>>> = vec![self.find_table(&tablename)?];
+        for join in &joins {
+            let table_handle = self.find_table(&join.table)?;
+            if table_handles.iter().any(|entry| Arc::ptr_eq(entry, &table_handle)) {
+                return Err(Error::other(format!("Table with name {} can only be part of a join once.", join.table)));
+            }
+            table_handles.push(table_handle);
+        }
+        let command_lvl1 = strip_leading_word(remainder.clone());
+        let (conditions, order) = if command_lvl1.0.as_str() == "where" {
+            let (conditions, order) = strip_order_by(command_lvl1.1)?;
+            // Subqueries are executed before any table is locked.
+            (Some(self.decode_conditions(conditions)?), order)
+        } else {
+            let (rest, order) = strip_order_by(remainder)?;
+            if !rest.trim().is_empty() {
+                return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'where'", rest)));
+            }
+            (None, order)
+        };
+        let tables = self.read_in_order(&table_handles)?;
+        let joined = JoinedTables { tables: tables.iter().map(|table| &**table).collect() };
+        let mut rows = joined.join_rows(&joins)?;
+        if let Some(conditions) = conditions {
+            rows = joined.filter_rows(rows, &conditions)?;
+        }
+        if let Some((columnname, descending)) = order {
+            rows = joined.order_rows(rows, columnname, descending)?;
+        }
+        let column_names: Vec<String> = if column_list.trim() == "*" {
+            vec!["*".to_string()]
         } else {
+            let no_parenthesis = strip_outer_parenthesis(&column_list)?;
+            split_outside_parenthesis(no_parenthesis, ",").into_iter().map(|name| name.trim().to_string()).collect()
+        };
+        return joined.select_data(column_names, rows);
+    }
+
+    /// Describes how the remainder of a select statement is executed. Every step is a row of the
+    /// returned `ResultSet`, in the order it is executed.
+    /// The access of every condition is the one the search chooses from its estimate, the
+    /// conditions themselves are evaluated in the order they are written. Nothing is searched,
+    /// the estimates only use the statistics of the indexes.
+    fn explain_select(&self, query: String) -> Result<Success, Error> {
+        let command_lvl1 = strip_leading_word(query.clone());
+        let distinct = command_lvl1.0.as_str() == "distinct";
+        let command_lvl2 = strip_column_list(if distinct { command_lvl1.1 } else { query })?;
+        let command_lvl3 = strip_leading_word(command_lvl2.1);
+        if !command_lvl3.0.as_str().contains("from") {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'from'.", command_lvl3.0)));
+        }
+        let command_lvl4 = strip_leading_word(command_lvl3.1);
+        if starts_with_join(&command_lvl4.1) {
+            return Err(Error::other("Explaining a select with joins is not supported."));
+        }
+        let table_handle = self.find_table(&command_lvl4.0)?;
+        let command_lvl5 = strip_leading_word(command_lvl4.1.clone());
+        let (conditions, order) = if command_lvl5.0.as_str() == "where" {
+            let (conditions, order) = strip_order_by(command_lvl5.1)?;
+            (Some(self.decode_conditions(conditions)?), order)
+        } else {
+            (None, strip_order_by(command_lvl4.1)?.1)
+        };
+        let table = table_handle.read();
+        if table.is_err() {
             return Err(Error::other("Couldn't lock table!"));
         }
+        let ok_table = table.unwrap();
+        let mut steps: Vec<(String, String, usize)> = Vec::new();
+        match conditions {
+            Some(conditions) => {
+                for (step, access, estimate) in ok_table.explain_conditions(&conditions)? {
+                    steps.push((format!("filter {}", step), access.describe(), estimate));
+                }
+            },
+            None => steps.push((format!("read {}", ok_table.name), "full scan".to_string(), ok_table.len())),
+        }
+        if let Some((columnname, descending)) = order {
+            let column_index = ok_table.search_for_column(columnname.clone())?;
+            let ordered = ok_table.columns[column_index].index.as_ref().map(|index| index.is_ordered()).unwrap_or(false);
+            let access = if ordered { "ordered index scan" } else { "sort" };
+            let direction = if descending { "desc" } else { "asc" };
+            let estimate = steps.last().unwrap().2;
+            steps.push((format!("order by {} {}", columnname, direction), access.to_string(), estimate));
+        }
+        if distinct {
+            let estimate = steps.last().unwrap().2;
+            steps.push(("distinct".to_string(), "hash".to_string(), estimate));
+        }
+        let mut step_column: Vec<Data> = Vec::new();
+        let mut operation_column: Vec<Data> = Vec::new();
+        let mut access_column: Vec<Data> = Vec::new();
+        let mut estimate_column: Vec<Data> = Vec::new();
+        for (counter, (operation, access, estimate)) in steps.into_iter().enumerate() {
+            step_column.push(Data::Float(counter as f64 + 1.0));
+            operation_column.push(Data::String(operation));
+            access_column.push(Data::String(access));
+            estimate_column.push(Data::Float(estimate as f64));
+        }
+        return Ok(Success::ResultSet(ResultSet::from(vec![
+            ("step".to_string(), step_column),
+            ("operation".to_string(), operation_column),
+            ("access".to_string(), access_column),
+            ("estimated_rows".to_string(), estimate_column),
+        ])));
     }
 
-    fn search_conditionals(&self, conditions: String, table_index: usize) -> Result<Vec<usize>, Error> {
-        let decoded_conditions = decode_list_conditions(conditions, self.split_pattern.clone())?;
-        let mut encoded_conditions = encode_list_conditions(decoded_conditions, self.split_pattern.clone())?;
-        let mut found_data: Vec<usize> = Vec::new();
-
-        // Len can only be:
-        // 0 = error, 
-        // 1 == one set of 'columname = data', 
-        // 3 == 'columname = data' 'and/not/or' 'other_columnname = other_data'
-        // >= 3 == more: 'and' 'name = data'
-        if encoded_conditions.len() == 0 {
-            return Err(Error::other("Invalid nql syntax."));
-        } else if encoded_conditions.len() == 1 {
-            let data_query = encoded_conditions.first();
-            if data_query.is_some() {
-                let name = &data_query.unwrap().0;
-                let data = &data_query.unwrap().1;
-                let table = self.tables[table_index].lock();
-                if table.is_ok() {
-                    let ok_table = table.unwrap();
-                    let search = ok_table.search_column_data(name.to_string(), data.clone())?;
-                    // as there is no other elements, no need for push, just set:
-                    found_data = search;
-                } else {
+    /// Reads every table out of the file at the path, in the format it was saved in. A directory
+    /// at the path is read as a file for each table.
+    ///
+    /// ## Errors
+    /// If a file couldn't be read or decrypted, or isn't a valid neithdb file.
+    fn read_file(&mut self) -> Result<Success, Error> {
+        let storage = self.database.storage.write();
+        if storage.is_err() {
+            return Err(Error::other("Couldn't lock storage settings!"));
+        }
+        let mut ok_storage = storage.unwrap();
+        ok_storage.layout = if ok_storage.path.is_dir() { StorageLayout::TablePerFile } else { StorageLayout::SingleFile };
+        let file = match ok_storage.layout {
+            StorageLayout::SingleFile => ok_storage.path.clone(),
+            StorageLayout::TablePerFile => ok_storage.path.join(dirard::MANIFEST),
+        };
+        let bytes = read_neithdb_bytes(&ok_storage, &file)?;
+        #[cfg(feature = "compression")]
+        {
+            ok_storage.compression = zipard::is_compressed(&bytes);
+        }
+        let bytes = decompress_neithdb_bytes(bytes)?;
+        let (decoded_tables, format, read_tables) = match ok_storage.layout {
+            StorageLayout::SingleFile => {
+                let (decoded_tables, format) = dirard::decode_neithdb_tables(&bytes)?;
+                (decoded_tables, format, SavedTables::new())
+            },
+            StorageLayout::TablePerFile => dirard::read_table_files(&ok_storage, &bytes)?,
+        };
+        if let Ok(mut saved_tables) = self.database.saved_tables.lock() {
+            *saved_tables = read_tables;
+        }
+        ok_storage.format = format;
+        let foreign_keys: ForeignKeys = decoded_tables.iter().map(|table| (table.name.clone(), table.referenced_tables())).collect();
+        let read_tables: Vec<Arc<RwLock<Table>>> = decoded_tables.into_iter().map(|table| Arc::new(RwLock::new(table))).collect();
+        let tables = self.database.tables.write();
+        if tables.is_ok() {
+            *tables.unwrap() = read_tables;
+            if let Ok(mut known_foreign_keys) = self.database.foreign_keys.lock() {
+                *known_foreign_keys = foreign_keys;
+            }
+            return Ok(Success::SuccessMessage(true));
+        } else {
+            return Err(Error::other("Couldn't lock tables!"));
+        }
+    }
+
+    /// Returns a shared handle to the table with the supplied name. It stays the same table if
+    /// other tables are created or deleted, or it is renamed.
+    ///
+    /// ## Errors
+    /// If the tables couldn't be locked or there is no table with the name.
+    fn find_table(&self, tablename: &str) -> Result<Arc<RwLock<Table>>, Error> {
+        for entry in self.get_tables()? {
+            let found = entry.read().is_ok_and(|table| table.name == tablename);
+            if found {
+                return Ok(entry);
+            }
+        }
+        return Err(Error::other(format!("Table with name {} not found.", tablename)));
+    }
+
+    /// Returns a shared handle to every other table the foreign keys of the table point to.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked, or a referenced table doesn't exist.
+    fn referenced_tables(&self, table_handle: &Arc<RwLock<Table>>) -> Result<Vec<Arc<RwLock<Table>>>, Error> {
+        let tablenames = match table_handle.read() {
+            Ok(table) => table.referenced_tables(),
+            Err(_) => return Err(Error::other("Couldn't lock table!")),
+        };
+        let mut out: Vec<Arc<RwLock<Table>>> = Vec::new();
+        for tablename in tablenames {
+            out.push(self.find_table(&tablename)?);
+        }
+        return Ok(out);
+    }
+
+    /// Returns the positions of the supplied tables, in the order they were created. Tables are
+    /// always locked in this order, never the order they are written in, so statements locking
+    /// more than one table never wait on each other.
+    ///
+    /// ## Errors
+    /// If the tables couldn't be locked, or a table was deleted in the meantime.
+    fn lock_order(&self, table_handles: &[&Arc<RwLock<Table>>]) -> Result<Vec<usize>, Error> {
+        let tables = self.get_tables()?;
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for (counter, table_handle) in table_handles.iter().enumerate() {
+            let position = tables.iter().position(|entry| Arc::ptr_eq(entry, table_handle));
+            if position.is_none() {
+                return Err(Error::other("Table was deleted in the meantime, aborting task, no data changed!"));
+            }
+            positions.push((position.unwrap(), counter));
+        }
+        positions.sort();
+        return Ok(positions.into_iter().map(|position| position.1).collect());
+    }
+
+    /// Locks every supplied table for reading, see `lock_order`.
+    ///
+    /// ## Returns
+    /// The locked tables, in the order they were supplied.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked.
+    fn read_in_order<'a>(&self, table_handles: &'a [Arc<RwLock<Table>>]) -> Result<Vec<RwLockReadGuard<'a, Table>>, Error> {
+        let mut tables: Vec<Option<RwLockReadGuard<Table>>> = table_handles.iter().map(|_| None).collect();
+        for position in self.lock_order(&table_handles.iter().collect::<Vec<_>>())? {
+            let table = table_handles[position].read();
+            if table.is_err() {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+            tables[position] = Some(table.unwrap());
+        }
+        return Ok(tables.into_iter().map(|table| table.unwrap()).collect());
+    }
+
+    /// Locks the table for writing, and every supplied other table for reading, see
+    /// `lock_order`.
+    ///
+    /// ## Returns
+    /// The locked table, along with the other tables in the order they were supplied.
+    ///
+    /// ## Errors
+    /// If a table couldn't be locked.
+    fn lock_in_order<'a>(&self, table_handle: &'a Arc<RwLock<Table>>, others: &'a [Arc<RwLock<Table>>]) -> Result<(RwLockWriteGuard<'a, Table>, Vec<RwLockReadGuard<'a, Table>>), Error> {
+        let mut table_handles: Vec<&Arc<RwLock<Table>>> = others.iter().collect();
+        table_handles.push(table_handle);
+        let mut table: Option<RwLockWriteGuard<Table>> = None;
+        let mut other_tables: Vec<Option<RwLockReadGuard<Table>>> = others.iter().map(|_| None).collect();
+        for position in self.lock_order(&table_handles)? {
+            if position == others.len() {
+                let locked = Neith::write_table(table_handle);
+                if locked.is_err() {
+                    return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+                }
+                table = Some(locked.unwrap());
+            } else {
+                let locked = others[position].read();
+                if locked.is_err() {
                     return Err(Error::other("Couldn't lock table!"));
                 }
+                other_tables[position] = Some(locked.unwrap());
+            }
+        }
+        return Ok((table.unwrap(), other_tables.into_iter().map(|table| table.unwrap()).collect()));
+    }
+
+    /// Returns a shared handle to every table, in the order they were created.
+    /// Tables created or deleted afterwards are not part of it.
+    ///
+    /// ## Errors
+    /// If the tables couldn't be locked.
+    fn get_tables(&self) -> Result<Vec<Arc<RwLock<Table>>>, Error> {
+        return self.database.get_tables();
+    }
+
+    /// Locks the table for writing. It only counts as changed once a statement succeeded on it,
+    /// see `count_change`.
+    fn write_table(table: &RwLock<Table>) -> LockResult<RwLockWriteGuard<'_, Table>> {
+        return table.write();
+    }
+
+    /// Counts the table as changed if the statement succeeded. A failed statement changes no
+    /// data, so the table isn't saved again because of it.
+    fn count_change<T>(table: &mut Table, result: Result<T, Error>) -> Result<T, Error> {
+        if result.is_ok() {
+            table.changes += 1;
+        }
+        return result;
+    }
+
+    /// Adds the table to the database, shared by every handle. Its name is checked while no other
+    /// table can be added or renamed.
+    ///
+    /// ## Errors
+    /// If the tables couldn't be locked, or there is a table with the same name already, with
+    /// `ErrorKind::AlreadyExists`.
+    fn push_table(&self, table: Arc<RwLock<Table>>) -> Result<(), Error> {
+        let (tablename, referenced) = match table.read() {
+            Ok(new_table) => (new_table.name.clone(), new_table.referenced_tables()),
+            Err(_) => return Err(Error::other("Couldn't lock table!")),
+        };
+        let tables = self.database.tables.write();
+        if tables.is_ok() {
+            let mut ok_tables = tables.unwrap();
+            if ok_tables.iter().any(|entry| entry.read().is_ok_and(|entry| entry.name == tablename)) {
+                return Err(Error::new(io::ErrorKind::AlreadyExists, format!("Table with name {} exists already.", tablename)));
+            }
+            if let Ok(mut saved_tables) = self.database.saved_tables.lock() {
+                let _ = saved_tables.remove(&tablename);
+            }
+            if let Ok(mut foreign_keys) = self.database.foreign_keys.lock() {
+                foreign_keys.push((tablename, referenced));
+            }
+            ok_tables.push(table);
+            return Ok(());
+        } else {
+            return Err(Error::other("Couldn't lock tables!"));
+        }
+    }
+
+    fn delete_table(&self, tablename: String) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        let tables = self.database.tables.write();
+        if tables.is_ok() {
+            let mut ok_tables = tables.unwrap();
+            // No foreign key pointing to it can be created while the tables are locked.
+            Neith::check_referenced(&ok_tables, &table_handle, &tablename, None)?;
+            let table_index = ok_tables.iter().position(|entry| Arc::ptr_eq(entry, &table_handle));
+            if table_index.is_none() {
+                return Err(Error::other(format!("Table with name {} not found.", tablename)));
+            }
+            let _ = ok_tables.remove(table_index.unwrap());
+            if let Ok(mut saved_tables) = self.database.saved_tables.lock() {
+                let _ = saved_tables.remove(&tablename);
+            }
+            if let Ok(mut foreign_keys) = self.database.foreign_keys.lock() {
+                let _ = foreign_keys.remove(table_index.unwrap());
+            }
+            return Ok(Success::SuccessMessage(true));
+        } else {
+            return Err(Error::other("Couldn't lock tables!"));
+        }
+    }
+
+    fn delete_column(&self, tablename: String, columnname: String) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        Neith::check_referenced(&self.get_tables()?, &table_handle, &tablename, Some(&columnname))?;
+        let table = Neith::write_table(&table_handle);
+        if table.is_ok() {
+            let mut ok_table = table.unwrap();
+            let answ = ok_table.delete_column(columnname);
+            let answ = Neith::count_change(&mut ok_table, answ)?;
+            self.database.update_foreign_keys(&ok_table);
+            if answ == Success::SuccessMessage(true) {
+                return Ok(Success::SuccessMessage(true));
             } else {
-                return Err(Error::other(format!("Invalid nql syntax: {:?} = should be a column name and data", encoded_conditions[0])));
-            }
-        } else if encoded_conditions.len() == 3 {
-            // fn tbd(input: Vec<(String, Data)>) -> Vec<(usize, Data)>
-            let data_query = &encoded_conditions[0];
-            let name = &data_query.0;
-            let data = &data_query.1;
-            let condition = &encoded_conditions[1].0;
-            let other_query = &encoded_conditions[2];
-            let other_name = &other_query.0;
-            let other_data = &other_query.1;
-            let table = self.tables[table_index].lock();
+                return Err(Error::other("Deletion error!"));
+            }
+        } else {
+            return Err(Error::other("Couldn't lock table!"));
+        }
+    }
+
+    /// Creates a new table holding every row of the table, along with its columns and check
+    /// constraints. Foreign keys of the table pointing to itself point to the copy instead.
+    ///
+    /// ## Errors
+    /// If the table doesn't exist, there is a table with the new name already, or a table couldn't
+    /// be locked.
+    fn copy_table(&self, tablename: String, new_name: String) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        if self.exists_table(new_name.clone())? {
+            return Err(Error::other(format!("Table with name {} exists already.", new_name)));
+        }
+        let mut new_table = {
+            let table = table_handle.read();
             if table.is_ok() {
-                let ok_table = table.unwrap();
-                let search = ok_table.search_column_data(name.to_string(), data.clone())?;
-                let other_search = ok_table.search_column_data(other_name.to_string(), other_data.clone())?;
-                let condition_check = condition_check(search, condition.to_string(), other_search)?;
-                // as there is no other elements, no need for push, just set:
-                found_data = condition_check;
+                table.unwrap().clone()
             } else {
                 return Err(Error::other("Couldn't lock table!"));
             }
-        } else if encoded_conditions.len() > 3 {
-            // fn tbd(input: Vec<(String, Data)>) -> Vec<(usize, Data)>
-            let data_query = encoded_conditions.remove(0);
-            let name = data_query.0;
-            let data = data_query.1;
-            // As I remove from the vec, 1 is now 0
-            let condition = encoded_conditions.remove(0).0;
-            // As I remove from the vec, 2 is now 0
-            let other_query = encoded_conditions.remove(0);
-            let other_name = other_query.0;
-            let other_data = other_query.1;
-            let table = self.tables[table_index].lock();
+        };
+        new_table.name = new_name.clone();
+        new_table.changes = 0;
+        let _ = new_table.rename_references(&tablename, None, &new_name);
+        self.push_table(Arc::new(RwLock::new(new_table)))?;
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Creates a new table out of the result of a select statement, the remainder of
+    /// `new table 'tablename' as select ...`. Every column selected under its own name keeps its
+    /// unique boolean, index and foreign key, any other entry becomes a column that is not unique.
+    /// A foreign key of the source table pointing to itself points to the new table, if the
+    /// referenced column is selected too. Computed entries and columns of a join need an alias to
+    /// name their column.
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql, there is a table with the new name already, an entry has no
+    /// valid column name, a selected row points to a row that isn't, or a table couldn't be
+    /// locked.
+    fn new_table_as_select(&self, tablename: String, query: String) -> Result<Success, Error> {
+        if self.exists_table(tablename.clone())? {
+            return Err(Error::other(format!("Table with name {} exists already.", tablename)));
+        }
+        let command_lvl1 = strip_leading_word(query.clone());
+        let without_distinct = if command_lvl1.0.as_str() == "distinct" { command_lvl1.1 } else { query.clone() };
+        let command_lvl2 = strip_column_list(without_distinct)?;
+        let command_lvl3 = strip_leading_word(command_lvl2.1);
+        if command_lvl3.0.as_str() != "from" {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'from'", command_lvl3.1)));
+        }
+        let source_handle = self.find_table(&strip_leading_word(command_lvl3.1).0)?;
+        let source = {
+            let table = source_handle.read();
             if table.is_ok() {
-                let ok_table = table.unwrap();
-                let search = ok_table.search_column_data(name, data)?;
-                let other_search = ok_table.search_column_data(other_name, other_data)?;
-                let mut temp_hit_files: Vec<usize>;
-                temp_hit_files = condition_check(search, condition, other_search)?;
-                let mut read_condition = String::new();
-                for entry in encoded_conditions {
-                    if entry.1 == Data::default() {
-                        // Has to be a conditional
-                        read_condition = entry.0;
-                    } else {
-                        let diff_name = entry.0;
-                        let diff_data = entry.1;
-                        let diff_search = ok_table.search_column_data(diff_name, diff_data)?;
-                        temp_hit_files = condition_check(temp_hit_files, read_condition.clone(), diff_search)?;
+                table.unwrap().clone()
+            } else {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+        };
+        let decoded_column_list = decode_column_list(command_lvl2.0, source.clone())?;
+        let selected = match self.select(query) {
+            Ok(answ) => answ,
+            // Nothing matched, the table is created without rows.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => source.select_entries(decoded_column_list.clone(), Vec::new())?,
+            Err(error) => return Err(error),
+        };
+        let named: Vec<(String, Data)> = match selected {
+            Success::ResultSet(answ) => answ.names.into_iter().zip(answ.columns).collect(),
+            Success::Result(answ) => {
+                // Selected columns are returned in the order of the table.
+                let names = source.columns.iter().filter(|column| decoded_column_list.contains(&column.name)).map(|column| column.name.clone());
+                names.zip(answ).collect()
+            },
+            Success::SuccessMessage(_) => return Err(Error::other("The select statement returned no data.")),
+        };
+        let own_names: Vec<String> = decoded_column_list.iter().filter(|entry| strip_alias(entry).1.is_none()).cloned().collect();
+        let mut columns: Vec<Column> = Vec::new();
+        for (columnname, data) in named {
+            // Columns of a join are named 'tablename.columnname', which reads as a column of another table.
+            if columnname.split_whitespace().count() != 1 || columnname.contains('.') {
+                return Err(Error::other(format!("{:?} is not a valid column name, name it with 'as'.", columnname)));
+            }
+            if columns.iter().any(|column| column.name == columnname) {
+                return Err(Error::other(format!("Column with name '{}' is selected more than once.", columnname)));
+            }
+            let all_row_data = data.get_list().unwrap_or_default();
+            match source.columns.iter().find(|column| column.name == columnname && own_names.contains(&columnname)) {
+                Some(column) => {
+                    let index_type = column.index.as_ref().map(|index| index.get_type());
+                    columns.push(Column::from_saved(columnname, column.unique, column.references.clone(), index_type.as_deref(), all_row_data));
+                },
+                None => columns.push(Column::from_saved(columnname, false, None, None, all_row_data)),
+            }
+        }
+        let mut new_table = Table::from((tablename.clone(), columns));
+        // Foreign keys to a column of the source that is part of the new table point to it
+        // instead, just like with a copy.
+        for column_index in 0..new_table.columns.len() {
+            let reference = new_table.columns[column_index].references.clone();
+            if reference.is_none() || reference.as_ref().unwrap().table != source.name {
+                continue;
+            }
+            let reference = reference.unwrap();
+            let referenced = new_table.search_for_column(reference.column.clone());
+            if referenced.is_err() {
+                continue;
+            }
+            let referenced_column = &new_table.columns[referenced.unwrap()];
+            let column = &new_table.columns[column_index];
+            let missing = column.contents.all_row_data.iter().find(|data| !data.is_null() && !referenced_column.contains(data));
+            if let Some(data) = missing {
+                return Err(Error::other(format!("Foreign key violation! {:?} of {} does not exist in {}({}), select the row it points to as well.", data, column.name, tablename, reference.column)));
+            }
+            new_table.columns[column_index].references.as_mut().unwrap().table = tablename.clone();
+        }
+        self.push_table(Arc::new(RwLock::new(new_table)))?;
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Decodes and executes an alter statement:
+    /// `table 'tablename' rename to 'new_name'`,
+    /// `column 'columnname' in 'tablename' rename to 'new_name'` or
+    /// `column 'columnname' in 'tablename' set unique true / false`
+    ///
+    /// ## Errors
+    /// If supplied with invalid nql, or the change isn't possible.
+    fn alter(&self, query: String) -> Result<Success, Error> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        match words.as_slice() {
+            ["table", tablename, "rename", "to", new_name] => return self.rename_table(tablename.to_string(), new_name.to_string()),
+            ["column", columnname, "in", tablename, "rename", "to", new_name] => return self.rename_column(tablename.to_string(), columnname.to_string(), new_name.to_string()),
+            ["column", columnname, "in", tablename, "set", "unique", unique] => {
+                let unique = match *unique {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'true' or 'false'", unique))),
+                };
+                let table_handle = self.find_table(tablename)?;
+                let table = Neith::write_table(&table_handle);
+                if table.is_ok() {
+                    let mut ok_table = table.unwrap();
+                    let answ = ok_table.set_unique(columnname.to_string(), unique);
+                    return Neith::count_change(&mut ok_table, answ);
+                } else {
+                    return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+                }
+            },
+            _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'table 'tablename' rename to 'new_name'' or 'column 'columnname' in 'tablename' rename to 'new_name' / set unique true/false'", query))),
+        }
+    }
+
+    /// Renames the table, and every foreign key pointing to it.
+    ///
+    /// ## Errors
+    /// If the table doesn't exist, there is a table with the new name already, or a table couldn't
+    /// be locked.
+    fn rename_table(&self, tablename: String, new_name: String) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        {
+            // No other table can be added or renamed while the tables are locked.
+            let tables = self.database.tables.write();
+            if tables.is_err() {
+                return Err(Error::other("Couldn't lock tables!"));
+            }
+            let ok_tables = tables.unwrap();
+            let taken = ok_tables.iter().any(|entry| !Arc::ptr_eq(entry, &table_handle) && entry.read().is_ok_and(|entry| entry.name == new_name));
+            if taken {
+                return Err(Error::other(format!("Table with name {} exists already.", new_name)));
+            }
+            let table = Neith::write_table(&table_handle);
+            if table.is_ok() {
+                let mut ok_table = table.unwrap();
+                ok_table.name = new_name.clone();
+                // Self-references are renamed with the table.
+                let _ = ok_table.rename_references(&tablename, None, &new_name);
+                ok_table.changes += 1;
+            } else {
+                return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+            }
+            if let Ok(mut foreign_keys) = self.database.foreign_keys.lock() {
+                for (name, referenced) in foreign_keys.iter_mut() {
+                    if name == &tablename {
+                        *name = new_name.clone();
+                    }
+                    for referenced_name in referenced.iter_mut().filter(|referenced_name| **referenced_name == tablename) {
+                        *referenced_name = new_name.clone();
                     }
                 }
-                // As the code above ended up exhaustive, again just set:
-                found_data = temp_hit_files;
+            }
+        }
+        for entry in self.get_tables()? {
+            let references = entry.read().is_ok_and(|table| table.references(&tablename, None));
+            if references {
+                let table = Neith::write_table(&entry);
+                if table.is_ok() {
+                    let mut ok_table = table.unwrap();
+                    let _ = ok_table.rename_references(&tablename, None, &new_name);
+                    ok_table.changes += 1;
+                } else {
+                    return Err(Error::other("Couldn't lock table!"));
+                }
+            }
+        }
+        if let Ok(mut saved_tables) = self.database.saved_tables.lock() {
+            let _ = saved_tables.remove(&tablename);
+            let _ = saved_tables.remove(&new_name);
+        }
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Renames the column of the table, along with its uses in check constraints and every
+    /// foreign key pointing to it.
+    ///
+    /// ## Errors
+    /// If the table or column doesn't exist, there is a column with the new name already, or a
+    /// table couldn't be locked.
+    fn rename_column(&self, tablename: String, columnname: String, new_name: String) -> Result<Success, Error> {
+        let table_handle = self.find_table(&tablename)?;
+        let table = Neith::write_table(&table_handle);
+        if table.is_ok() {
+            let mut ok_table = table.unwrap();
+            let answ = ok_table.rename_column(columnname.clone(), new_name.clone());
+            let _ = Neith::count_change(&mut ok_table, answ)?;
+        } else {
+            return Err(Error::other("Couldn't lock Table! Aborting task, no data changed!"));
+        }
+        for entry in self.get_tables()? {
+            let references = entry.read().is_ok_and(|table| table.references(&tablename, Some(&columnname)));
+            if references {
+                let table = Neith::write_table(&entry);
+                if table.is_ok() {
+                    let mut ok_table = table.unwrap();
+                    let _ = ok_table.rename_references(&tablename, Some(&columnname), &new_name);
+                    ok_table.changes += 1;
+                } else {
+                    return Err(Error::other("Couldn't lock table!"));
+                }
+            }
+        }
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Makes sure every foreign key of the supplied columns points to an existing column. The
+    /// columns may also reference the table they are a part of.
+    ///
+    /// ## Errors
+    /// If a referenced table or column does not exist.
+    fn validate_references(&self, tablename: &String, columns: &Vec<Column>) -> Result<(), Error> {
+        for column in columns {
+            if column.references.is_none() {
+                continue;
+            }
+            let reference = column.references.as_ref().unwrap();
+            if &reference.table == tablename && columns.iter().any(|entry| entry.name == reference.column) {
+                continue;
+            }
+            let table_handle = self.find_table(&reference.table)?;
+            let table = table_handle.read();
+            if table.is_ok() {
+                let _ = table.unwrap().search_for_column(reference.column.clone())?;
             } else {
                 return Err(Error::other("Couldn't lock table!"));
             }
         }
-        return Ok(found_data);
+        return Ok(());
     }
 
-    fn search_for_table(&self, tablename: String) -> Result<usize, Error> {
-        let mut counter: usize = 0;
-        for entry in &self.tables {
-            let table = entry.lock();
+    /// Checks the supplied data against the foreign keys of the table. The caller locks every
+    /// other table they point to, a foreign key pointing to the table itself is checked against it
+    /// along with the supplied data, as rows of the same statement can point to each other.
+    /// `Null` is always allowed.
+    ///
+    /// ## Errors
+    /// If a value does not exist in the referenced column.
+    fn check_references(table: &Table, referenced: &[RwLockReadGuard<Table>], value: &[(String, Data)]) -> Result<(), Error> {
+        for column in &table.columns {
+            if column.references.is_none() {
+                continue;
+            }
+            let reference = column.references.as_ref().unwrap();
+            let to_check: Vec<&Data> = value.iter().filter(|entry| entry.0 == column.name && !entry.1.is_null()).map(|entry| &entry.1).collect();
+            if to_check.is_empty() {
+                continue;
+            }
+            let referenced_table = if reference.table == table.name {
+                Some(table)
+            } else {
+                referenced.iter().find(|entry| entry.name == reference.table).map(|entry| &**entry)
+            };
+            if referenced_table.is_none() {
+                return Err(Error::other(format!("Table with name {} not found.", reference.table)));
+            }
+            let referenced_table = referenced_table.unwrap();
+            let column_index = referenced_table.search_for_column(reference.column.clone())?;
+            let own_entries: HashSet<IndexKey> = if reference.table == table.name {
+                value.iter().filter(|entry| entry.0 == reference.column).map(|entry| IndexKey(entry.1.clone())).collect()
+            } else {
+                HashSet::new()
+            };
+            for data in to_check {
+                if !own_entries.contains(&IndexKey(data.clone())) && !referenced_table.columns[column_index].contains(data) {
+                    return Err(Error::other(format!("Foreign key violation! {:?} does not exist in {}({}).", data, reference.table, reference.column)));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Checks if any other of the supplied tables holds a foreign key pointing to the table, or
+    /// only to the supplied column of it.
+    ///
+    /// ## Errors
+    /// If it is referenced.
+    fn check_referenced(tables: &[Arc<RwLock<Table>>], table_handle: &Arc<RwLock<Table>>, tablename: &String, columnname: Option<&String>) -> Result<(), Error> {
+        for entry in tables {
+            if Arc::ptr_eq(entry, table_handle) {
+                continue;
+            }
+            let table = entry.read();
             if table.is_ok() {
                 let ok_table = table.unwrap();
-                if ok_table.name.eq(&tablename) {
-                    return Ok(counter);
+                for column in &ok_table.columns {
+                    if let Some(reference) = &column.references {
+                        if &reference.table == tablename && (columnname.is_none() || columnname == Some(&reference.column)) {
+                            return Err(Error::other(format!("{}({}) is referenced by {}({}), aborting task, no data changed!", reference.table, reference.column, ok_table.name, column.name)));
+                        }
+                    }
                 }
+            } else {
+                return Err(Error::other("Couldn't lock table!"));
             }
-            counter += 1;
         }
-        return Err(Error::other(format!("Table with name {} not found.", tablename)));
+        return Ok(());
     }
 
-    fn delete_table(&mut self, tablename: String) -> Result<Success, Error> {
-        let _ = self.tables.remove(self.search_for_table(tablename)?);
-        return Ok(Success::SuccessMessage(true))
+    /// Collects the table at the supplied position, along with every table with a foreign key
+    /// pointing to it or to another collected table. No table is locked, the foreign keys are
+    /// known while the tables are locked.
+    ///
+    /// ## Returns
+    /// The positions of the collected tables, in the order they were created.
+    fn referencing_tables(foreign_keys: &ForeignKeys, position: usize) -> Vec<usize> {
+        let mut collected: Vec<bool> = vec![false; foreign_keys.len()];
+        collected[position] = true;
+        let mut queue: Vec<usize> = vec![position];
+        while let Some(current) = queue.pop() {
+            let tablename = &foreign_keys[current].0;
+            for (other, (_, referenced)) in foreign_keys.iter().enumerate() {
+                if !collected[other] && referenced.contains(tablename) {
+                    collected[other] = true;
+                    queue.push(other);
+                }
+            }
+        }
+        return (0..foreign_keys.len()).filter(|other| collected[*other]).collect();
     }
 
-    fn delete_column(&mut self, tablename: String, columnname: String) -> Result<Success, Error> {
-        let table_index = self.search_for_table(tablename)?;
-        let table = self.tables[table_index].lock();
-        if table.is_ok() {
-            let mut ok_table = table.unwrap();
-            let answ = ok_table.delete_column(columnname)?;
-            if answ == Success::SuccessMessage(true) {
-                return Ok(Success::SuccessMessage(true));
+    /// Deletes the rows selected by the supplied function from the table, and follows the
+    /// `on delete` behaviour of any foreign key pointing at them through all tables.
+    /// The table, and every table referencing it directly or through other tables, are locked
+    /// for writing in the order they were created, until the rows are deleted. The rows are
+    /// selected while it is locked. Every other table stays unlocked.
+    /// Nothing is changed until every affected row is known and every `set null` is checked, so a
+    /// `restrict` or a violated constraint will leave all tables untouched.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the table was deleted, a referencing foreign key is set to `restrict`, or setting it to
+    /// `Null` violates a constraint.
+    fn delete_rows<F>(&self, table_handle: &Arc<RwLock<Table>>, select: F) -> Result<Success, Error> where F: Fn(&Table) -> Result<Vec<usize>, Error> {
+        // No table can be created until all of them are locked, it could point to the rows.
+        let all_tables = self.database.tables.read();
+        if all_tables.is_err() {
+            return Err(Error::other("Couldn't lock tables!"));
+        }
+        let all_tables = all_tables.unwrap();
+        let target = all_tables.iter().position(|entry| Arc::ptr_eq(entry, table_handle));
+        if target.is_none() {
+            return Err(Error::other("Table was deleted in the meantime, aborting task, no data changed!"));
+        }
+        let foreign_keys = match self.database.foreign_keys.lock() {
+            Ok(foreign_keys) => foreign_keys.clone(),
+            Err(_) => return Err(Error::other("Couldn't lock foreign keys!")),
+        };
+        if foreign_keys.len() != all_tables.len() {
+            return Err(Error::other("Foreign keys don't match the tables, aborting task, no data changed!"));
+        }
+        let table_handles: Vec<Arc<RwLock<Table>>> = Neith::referencing_tables(&foreign_keys, target.unwrap()).into_iter().map(|position| all_tables[position].clone()).collect();
+        let table_index = table_handles.iter().position(|entry| Arc::ptr_eq(entry, table_handle)).unwrap();
+        let mut tables: Vec<RwLockWriteGuard<Table>> = Vec::new();
+        for entry in &table_handles {
+            let table = entry.write();
+            if table.is_err() {
+                return Err(Error::other("Couldn't lock table!"));
+            }
+            tables.push(table.unwrap());
+        }
+        drop(all_tables);
+        let indicies = select(&tables[table_index])?;
+        let mut to_delete: Vec<Vec<usize>> = vec![Vec::new(); tables.len()];
+        let mut to_null: Vec<(usize, String, Vec<usize>)> = Vec::new();
+        let mut queue: Vec<(usize, Vec<usize>)> = vec![(table_index, indicies)];
+        while let Some((current_index, rows)) = queue.pop() {
+            let new_rows: Vec<usize> = rows.into_iter().filter(|row| !to_delete[current_index].contains(row)).collect();
+            if new_rows.is_empty() {
+                continue;
+            }
+            to_delete[current_index].extend(new_rows.iter());
+            // The deleted rows are copied out first, the table could reference itself.
+            let tablename = tables[current_index].name.clone();
+            let mut deleted_rows: Vec<(String, Vec<Data>)> = Vec::new();
+            for column in &tables[current_index].columns {
+                let values: Vec<Data> = new_rows.iter().map(|row| column.contents.all_row_data[*row].clone()).collect();
+                deleted_rows.push((column.name.clone(), values));
+            }
+            for (child_index, ok_table) in tables.iter().enumerate() {
+                for column in &ok_table.columns {
+                    if column.references.is_none() || column.references.as_ref().unwrap().table != tablename {
+                        continue;
+                    }
+                    let reference = column.references.as_ref().unwrap();
+                    let deleted_keys = deleted_rows.iter().find(|entry| entry.0 == reference.column);
+                    if deleted_keys.is_none() {
+                        continue;
+                    }
+                    let deleted_keys = &deleted_keys.unwrap().1;
+                    let mut referencing: Vec<usize> = Vec::new();
+                    for (row, data) in column.contents.all_row_data.iter().enumerate() {
+                        if !data.is_null() && deleted_keys.contains(data) {
+                            referencing.push(row);
+                        }
+                    }
+                    if referencing.is_empty() {
+                        continue;
+                    }
+                    match reference.on_delete {
+                        OnDelete::Restrict => {
+                            return Err(Error::other(format!("{}({}) is still referenced by {}({}), aborting task, no data changed!", tablename, reference.column, ok_table.name, column.name)));
+                        },
+                        OnDelete::Cascade => queue.push((child_index, referencing)),
+                        OnDelete::SetNull => to_null.push((child_index, column.name.clone(), referencing)),
+                    }
+                }
+            }
+        }
+        // Every table is checked before any of them is changed.
+        for (index, table) in tables.iter().enumerate() {
+            let updates: Vec<(String, Vec<usize>)> = to_null.iter().filter(|entry| entry.0 == index).map(|entry| (entry.1.clone(), entry.2.clone())).collect();
+            if updates.is_empty() {
+                continue;
+            }
+            table.check_set_null(&updates)?;
+        }
+        // Nulling first, deleting shifts the rows.
+        for (child_index, columnname, rows) in to_null {
+            let ok_table = &mut tables[child_index];
+            let _ = ok_table.update_data(vec![(columnname, Data::Null())], rows)?;
+            ok_table.changes += 1;
+        }
+        for (index, rows) in to_delete.into_iter().enumerate() {
+            if rows.is_empty() {
+                continue;
+            }
+            let ok_table = &mut tables[index];
+            ok_table.changes += 1;
+            // Every row is deleted, no need to shift them one by one.
+            if rows.len() == ok_table.len() {
+                let _ = ok_table.truncate();
             } else {
-                return Err(Error::other("Deletion error!"));
+                let _ = ok_table.delete_data(rows)?;
             }
-        } else {
-            return Err(Error::other("Couldn't lock table!"));
         }
+        return Ok(Success::SuccessMessage(true));
     }
 
 }
-
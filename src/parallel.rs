@@ -0,0 +1,92 @@
+//! Splits the work over the rows of a column between threads, used with the `parallel` feature.
+//! Every function returns exactly what reading the rows one after the other would.
+
+use std::thread;
+
+use crate::{data::Data, index::is_comparable};
+
+/// Columns are never split into chunks smaller than this, starting a thread would take longer
+/// than reading them. Shorter columns are read on the calling thread.
+pub const MIN_CHUNK_SIZE: usize = 1024;
+
+/// Returns the length of the chunks the rows are split into, one chunk for every available
+/// thread.
+fn chunk_size(rows: usize) -> usize {
+    let threads = thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1);
+    return rows.div_ceil(threads).max(MIN_CHUNK_SIZE);
+}
+
+/// Returns the indices of all entries fulfilling the predicate, in ascending order.
+pub fn scan<F>(all_row_data: &[Data], predicate: F) -> Vec<usize> where F: Fn(&Data) -> bool + Sync {
+    return scan_chunks(all_row_data, chunk_size(all_row_data.len()), predicate);
+}
+
+/// Like `scan`, with the length of the chunks supplied. A single chunk is read without starting
+/// a thread.
+pub fn scan_chunks<F>(all_row_data: &[Data], size: usize, predicate: F) -> Vec<usize> where F: Fn(&Data) -> bool + Sync {
+    let size = size.max(1);
+    if size >= all_row_data.len() {
+        return (0..all_row_data.len()).filter(|row| predicate(&all_row_data[*row])).collect();
+    }
+    let predicate = &predicate;
+    return thread::scope(|scope| {
+        let mut threads = Vec::new();
+        for (chunk_index, chunk) in all_row_data.chunks(size).enumerate() {
+            threads.push(scope.spawn(move || {
+                let mut out: Vec<usize> = Vec::new();
+                for (counter, entry) in chunk.iter().enumerate() {
+                    if predicate(entry) {
+                        out.push(chunk_index * size + counter);
+                    }
+                }
+                return out;
+            }));
+        }
+        let mut out: Vec<usize> = Vec::new();
+        for found in threads {
+            out.extend(found.join().unwrap());
+        }
+        return out;
+    });
+}
+
+/// Returns the smallest, or with `largest` the largest, entry of the column.
+///
+/// ## Returns
+/// `None` if the column is too short to be split up.
+pub fn min_max(all_row_data: &[Data], largest: bool) -> Option<Data> {
+    let size = chunk_size(all_row_data.len());
+    if size >= all_row_data.len() {
+        return None;
+    }
+    return min_max_chunks(all_row_data, size, largest);
+}
+
+/// Like `min_max`, with the length of the chunks supplied.
+///
+/// ## Returns
+/// `None` if the column has no entry that can be compared.
+pub fn min_max_chunks(all_row_data: &[Data], size: usize, largest: bool) -> Option<Data> {
+    /// The sequential search: the first comparable entry wins, until a strictly smaller (or
+    /// larger) one is found. Entries that can't be compared, like NaN, are skipped.
+    fn fold<'a, I>(chunk: I, largest: bool) -> Option<&'a Data> where I: Iterator<Item = &'a Data> {
+        let mut out: Option<&Data> = None;
+        for data in chunk.filter(|data| is_comparable(data)) {
+            if out.is_none() || (largest && data > out.unwrap()) || (!largest && data < out.unwrap()) {
+                out = Some(data);
+            }
+        }
+        return out;
+    }
+    let size = size.max(1);
+    // Without the entries that can't be compared the order is total, and folding the chunk
+    // results in order returns the same entry as folding every row.
+    let chunk_results: Vec<Option<&Data>> = thread::scope(|scope| {
+        let mut threads = Vec::new();
+        for chunk in all_row_data.chunks(size) {
+            threads.push(scope.spawn(move || fold(chunk.iter(), largest)));
+        }
+        return threads.into_iter().map(|found| found.join().unwrap()).collect();
+    });
+    return fold(chunk_results.into_iter().flatten(), largest).cloned();
+}
@@ -0,0 +1,95 @@
+use crate::{data::Data, table::Table, column::Column, success::{Success, ResultSet}};
+
+/// Describes a table, as returned by `Neith::schema()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableSchema {
+    pub name: String,
+    pub row_count: usize,
+    pub columns: Vec<ColumnSchema>,
+    /// The name of every check constraint of the table.
+    pub checks: Vec<String>,
+}
+
+/// Describes a column of a table. Columns in Neith have no type, any entry can hold any type of
+/// `Data`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub unique: bool,
+    /// `hash` or `ordered`, if the column has an index.
+    pub index: Option<String>,
+    /// The foreign key of the column, written just like it is declared:
+    /// `tablename(columnname) on delete restrict`
+    pub references: Option<String>,
+}
+
+impl From<&Column> for ColumnSchema {
+
+    fn from(value: &Column) -> Self {
+        let index = value.index.as_ref().map(|index| index.get_type());
+        let references = value.references.as_ref().map(|reference| format!("{}({}) on delete {}", reference.table, reference.column, reference.on_delete.to_nql()));
+        return ColumnSchema { name: value.name.clone(), unique: value.unique, index, references };
+    }
+
+}
+
+impl From<&Table> for TableSchema {
+
+    fn from(value: &Table) -> Self {
+        let columns: Vec<ColumnSchema> = value.columns.iter().map(ColumnSchema::from).collect();
+        let checks: Vec<String> = value.checks.iter().map(|check| check.name.clone()).collect();
+        return TableSchema { name: value.name.clone(), row_count: value.len(), columns, checks };
+    }
+
+}
+
+impl TableSchema {
+
+    /// Describes every column of the table, as returned by `describe 'tablename'`.
+    ///
+    /// ## Returns
+    /// A `ResultSet` with the columns `column`, `unique`, `index` and `references`, one row per
+    /// column of the table. Missing indexes and foreign keys are `Null`.
+    pub fn describe(&self) -> Success {
+        let mut name_column: Vec<Data> = Vec::new();
+        let mut unique_column: Vec<Data> = Vec::new();
+        let mut index_column: Vec<Data> = Vec::new();
+        let mut references_column: Vec<Data> = Vec::new();
+        for column in &self.columns {
+            name_column.push(Data::String(column.name.clone()));
+            unique_column.push(Data::Bool(column.unique));
+            index_column.push(column.index.clone().map(Data::String).unwrap_or_default());
+            references_column.push(column.references.clone().map(Data::String).unwrap_or_default());
+        }
+        return Success::ResultSet(ResultSet::from(vec![
+            ("column".to_string(), name_column),
+            ("unique".to_string(), unique_column),
+            ("index".to_string(), index_column),
+            ("references".to_string(), references_column),
+        ]));
+    }
+
+}
+
+/// Lists every table, as returned by `show tables`.
+///
+/// ## Returns
+/// A `ResultSet` with the columns `table`, `rows`, `columns` and `checks`, one row per table.
+pub fn show_tables(tables: &[TableSchema]) -> Success {
+    let mut name_column: Vec<Data> = Vec::new();
+    let mut row_column: Vec<Data> = Vec::new();
+    let mut column_column: Vec<Data> = Vec::new();
+    let mut check_column: Vec<Data> = Vec::new();
+    for table in tables {
+        name_column.push(Data::String(table.name.clone()));
+        row_column.push(Data::Float(table.row_count as f64));
+        column_column.push(Data::Float(table.columns.len() as f64));
+        check_column.push(Data::List(table.checks.iter().map(|check| Data::String(check.clone())).collect()));
+    }
+    return Success::ResultSet(ResultSet::from(vec![
+        ("table".to_string(), name_column),
+        ("rows".to_string(), row_column),
+        ("columns".to_string(), column_column),
+        ("checks".to_string(), check_column),
+    ]));
+}
@@ -1,14 +1,40 @@
-use crate::data::Data;
+use std::collections::HashSet;
+
+use crate::{data::Data, index::IndexKey};
 
 #[derive(Clone, PartialEq, Debug)]
 /// Neith will either return a error, or this `Success` enum.
 /// `Success` can contain the general `SucessMessage` wrapping a boolean or the requested Result,
-/// wrapping a vector of `Data`.
+/// wrapping a vector of `Data`, or a `ResultSet` naming the columns of the result.
 pub enum Success {
     /// The wrapped boolean can be disregarded, it is never set to `false` but helps with debugging.
     SuccessMessage(bool),
     /// The wrapped vector contains the requested data, these are often nested vectors!
     Result(Vec<Data>),
+    /// The requested data, along with the name of each column.
+    ResultSet(ResultSet),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+/// A result with named columns. Each entry of `columns` is a `Data::List` containing the
+/// entries of the column with the name at the same position in `names`.
+pub struct ResultSet {
+    pub names: Vec<String>,
+    pub columns: Vec<Data>,
+}
+
+impl From<Vec<(String, Vec<Data>)>> for ResultSet {
+
+    fn from(value: Vec<(String, Vec<Data>)>) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        let mut columns: Vec<Data> = Vec::new();
+        for (name, column) in value {
+            names.push(name);
+            columns.push(Data::List(column));
+        }
+        return ResultSet { names, columns };
+    }
+
 }
 
 impl Success {
@@ -20,6 +46,7 @@ impl Success {
     pub fn is_result(&self) -> bool {
         match self {
             Success::Result(_data) => true,
+            Success::ResultSet(_data) => true,
             _ => false,
         }
     }
@@ -31,9 +58,47 @@ impl Success {
     pub fn get_result(&self) -> Option<Vec<Data>> {
         match self {
             Success::Result(answ) => return Some(answ.to_owned()),
+            Success::ResultSet(answ) => return Some(answ.columns.to_owned()),
+            _ => None,
+        }
+    }
+
+    /// Function to get the names of the columns of a Success wrapping a `ResultSet`.
+    ///
+    /// ## Returns
+    /// `Some(Vec<String>)` if a `ResultSet` exists, `None` otherwise.
+    pub fn get_column_names(&self) -> Option<Vec<String>> {
+        match self {
+            Success::ResultSet(answ) => return Some(answ.names.to_owned()),
             _ => None,
         }
     }
 
+    /// Removes every row of a result that is equal to a row before it, the first one is kept.
+    /// Anything else is returned as it is.
+    pub fn distinct(self) -> Self {
+        match self {
+            Success::Result(columns) => return Success::Result(distinct_rows(columns)),
+            Success::ResultSet(answ) => return Success::ResultSet(ResultSet { names: answ.names, columns: distinct_rows(answ.columns) }),
+            other => return other,
+        }
+    }
+
 }
 
+/// Removes every row that is equal to a row before it, out of the columns of a result.
+fn distinct_rows(columns: Vec<Data>) -> Vec<Data> {
+    let columns: Vec<Vec<Data>> = columns.into_iter().map(|column| column.get_list().unwrap_or_default()).collect();
+    let rows = columns.first().map(|column| column.len()).unwrap_or(0);
+    let mut seen: HashSet<IndexKey> = HashSet::new();
+    let mut out: Vec<Vec<Data>> = vec![Vec::new(); columns.len()];
+    for row in 0..rows {
+        let entries: Vec<Data> = columns.iter().map(|column| column[row].clone()).collect();
+        if seen.insert(IndexKey(Data::List(entries))) {
+            for (position, column) in columns.iter().enumerate() {
+                out[position].push(column[row].clone());
+            }
+        }
+    }
+    return out.into_iter().map(Data::List).collect();
+}
@@ -1,13 +1,19 @@
-use std::io::Error;
+use std::{io::{Error, ErrorKind}, cmp::Ordering, collections::HashSet};
 
 use json::JsonValue;
 
-use crate::{column::Column, success::Success, data::Data};
+use crate::{column::Column, success::{Success, ResultSet}, expression::{Expression, decode_expression}, data::Data, constraint::{Check, OnConflict}, condition::{Condition, Operator, evaluate_conditions}, index::{Index, IndexKey, Access, is_comparable}, utils::{util::{condition_check, strip_alias}, jisard::decode_data_to_jsonval}};
+#[cfg(feature = "parallel")]
+use crate::parallel;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
+    pub checks: Vec<Check>,
+    /// Counts every statement that changed the table, saving only encodes tables that changed
+    /// since the last save.
+    pub changes: u64,
 }
 
 impl Default for Table {
@@ -15,7 +21,8 @@ impl Default for Table {
     fn default() -> Self {
         let name = String::new();
         let columns: Vec<Column> = Vec::new();
-        return Table {name, columns, };
+        let checks: Vec<Check> = Vec::new();
+        return Table {name, columns, checks, changes: 0, };
     }
 
 }
@@ -25,10 +32,18 @@ impl From<(&str, &JsonValue)> for Table {
     fn from(value: (&str, &JsonValue)) -> Self {
         let name = value.0.to_string();
         let mut out: Vec<Column> = Vec::new();
+        let mut checks: Vec<Check> = Vec::new();
         for column in value.1.entries() {
+            // Column names can't contain whitespace, so this can never be a column.
+            if column.0 == "check constraints" {
+                for check in column.1.members() {
+                    checks.push(Check::from_json_value(check).expect("Invalid check constraint!"));
+                }
+                continue;
+            }
             out.push(Column::from_neithdb_column_data(column));
         }
-        return Table{name, columns: out};
+        return Table{name, columns: out, checks, changes: 0};
     }
 
 }
@@ -48,6 +63,21 @@ impl From<(String, Vec<(String, bool)>)> for Table {
         return Table {
             name: tablename,
             columns,
+            checks: Vec::new(),
+            changes: 0,
+        };
+    }
+
+}
+
+impl From<(String, Vec<Column>)> for Table {
+
+    fn from(value: (String, Vec<Column>)) -> Self {
+        return Table {
+            name: value.0,
+            columns: value.1,
+            checks: Vec::new(),
+            changes: 0,
         };
     }
 
@@ -59,9 +89,8 @@ impl Table {
     ///
     /// ## Returns
     /// A generic `SuccessMessage`
-    pub fn new_columns(&mut self, value: Vec<(String, bool)>) -> Success {
-        for entry in value {
-            let new_column = Column::from(entry);
+    pub fn new_columns(&mut self, value: Vec<Column>) -> Success {
+        for new_column in value {
             self.columns.push(new_column);
         }
         return Success::SuccessMessage(true);
@@ -75,42 +104,112 @@ impl Table {
     /// ## Errors
     /// If the supplied column doesn't exist.
     pub fn delete_data(&mut self, mut indicies: Vec<usize>) -> Result<Success, Error> {
-        // Forgot that deleting an entry moves the entire vector to the left by one; this is now
-        // included.
+        // Deleting an entry moves every entry after it to the left, all rows are deleted at once
+        // so they are only moved one time.
         indicies.sort();
-        let mut counter: usize = 0;
-        for index in indicies {
-            for column in &mut self.columns {
-                column.delete_data(index - counter);
-            }
-            counter += 1;
+        indicies.dedup();
+        for column in &mut self.columns {
+            let _ = column.delete_rows(&indicies);
         }
         return Ok(Success::SuccessMessage(true));
     }
 
+    /// Deletes every row of this table, keeping its columns, indexes and check constraints.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    pub fn truncate(&mut self) -> Success {
+        for column in &mut self.columns {
+            let _ = column.truncate();
+        }
+        return Success::SuccessMessage(true);
+    }
+
     /// Update data from columns of this table.
+    /// If any unique or check constraint is violated, all rows are restored.
     ///
     /// ## Returns
     /// A generic `SuccessMessage`.
     ///
     /// ## Errors
-    /// If the supplied column doesn't exist.
+    /// If the supplied column doesn't exist, or a constraint is violated.
     pub fn update_data(&mut self, value: Vec<(String, Data)>, indicies: Vec<usize>) -> Result<Success, Error> {
+        let per_row: Vec<(String, Vec<Data>)> = value.into_iter().map(|entry| (entry.0, vec![entry.1; indicies.len()])).collect();
+        return self.update_each_row(per_row, indicies);
+    }
+
+    /// Update data from columns of this table, every column is given its new data for each of
+    /// the rows, in the same order.
+    /// If any unique or check constraint is violated, all rows are restored.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist, or a constraint is violated.
+    pub fn update_each_row(&mut self, value: Vec<(String, Vec<Data>)>, indicies: Vec<usize>) -> Result<Success, Error> {
         let name_vec: Vec<String> = value.iter().map(|entry| {entry.0.clone()}).collect();
+        let backup: Vec<(usize, Vec<Data>)> = self.columns.iter().enumerate()
+            .filter(|column| name_vec.contains(&column.1.name))
+            .map(|column| (column.0, indicies.iter().map(|index| column.1.contents.all_row_data[*index].clone()).collect()))
+            .collect();
+        let answ = self.update_rows(value, &indicies);
+        if answ.is_err() {
+            for (column_index, old_data) in backup {
+                for (index, data) in indicies.iter().zip(old_data) {
+                    let _ = self.columns[column_index].contents.update_data(*index, data);
+                }
+                self.columns[column_index].rebuild_index();
+            }
+        }
+        return answ;
+    }
+
+    /// Writes the updated data, and checks the constraints of the changed rows.
+    fn update_rows(&mut self, value: Vec<(String, Vec<Data>)>, indicies: &Vec<usize>) -> Result<Success, Error> {
         for column in &mut self.columns {
-            if name_vec.contains(&column.name) {
-                for index in indicies.clone() {
-                    for entry in value.clone() {
-                        if column.name == entry.0 {
-                            let _ = column.update_data(index, entry.1)?;
-                        }
+            for entry in &value {
+                if column.name == entry.0 {
+                    for (index, data) in indicies.iter().zip(&entry.1) {
+                        let _ = column.update_data(*index, data.clone())?;
                     }
                 }
             }
         }
+        self.check_constraints(indicies)?;
         return Ok(Success::SuccessMessage(true));
     }
 
+    /// Computes the new data of an update for each of the rows. Every expression reads the rows
+    /// as they are before the update.
+    ///
+    /// ## Returns
+    /// Every column along with its new data, in the order of the rows.
+    ///
+    /// ## Errors
+    /// If an expression couldn't be computed.
+    pub fn compute_updates(&self, value: &[(String, Expression)], indicies: &[usize]) -> Result<Vec<(String, Vec<Data>)>, Error> {
+        let mut out: Vec<(String, Vec<Data>)> = Vec::new();
+        for (columnname, expression) in value {
+            let mut computed: Vec<Data> = Vec::new();
+            for index in indicies {
+                computed.push(self.compute(expression, *index)?);
+            }
+            out.push((columnname.clone(), computed));
+        }
+        return Ok(out);
+    }
+
+    /// Computes the expression for a single row.
+    ///
+    /// ## Errors
+    /// If the expression couldn't be computed.
+    pub fn compute(&self, expression: &Expression, row: usize) -> Result<Data, Error> {
+        return expression.evaluate(&|name| {
+            return self.columns.iter().find(|column| column.name == name).map(|column| column.contents.all_row_data[row].clone());
+        });
+    }
+
     /// Select data from columns of this table.
     ///
     /// ## Returns
@@ -142,14 +241,187 @@ impl Table {
         return Success::Result(found_data);
     }
 
+    /// Select data from columns of this table, or computed out of them. Every entry can be
+    /// renamed: `'columnname' as 'alias'`. If an entry of the list is computed or renamed, every
+    /// entry is returned in the order of the list, see `select_computed`.
+    ///
+    /// ## Errors
+    /// If a computed entry is invalid or couldn't be computed.
+    pub fn select_entries(&self, coulumn_names: Vec<String>, indicies: Vec<usize>) -> Result<Success, Error> {
+        let mut named = false;
+        let mut entries: Vec<(String, Expression)> = Vec::new();
+        for entry in &coulumn_names {
+            let (name, alias) = strip_alias(entry);
+            let computed = decode_expression(&name).ok().filter(|expression| expression.is_computed());
+            let expression = if computed.is_some() && self.search_for_column(name.clone()).is_err() {
+                computed.unwrap()
+            } else {
+                Expression::Word(name.clone())
+            };
+            named = named || alias.is_some() || expression.is_computed();
+            entries.push((alias.unwrap_or(name), expression));
+        }
+        if !named {
+            return Ok(self.select_data(coulumn_names, indicies));
+        }
+        return self.select_computed(entries, indicies);
+    }
+
+    /// Computes every entry for each of the rows.
+    ///
+    /// ## Returns
+    /// A `ResultSet` naming every entry by its alias or as it was written, in the order of the
+    /// list.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or an entry couldn't be computed.
+    pub fn select_computed(&self, entries: Vec<(String, Expression)>, indicies: Vec<usize>) -> Result<Success, Error> {
+        let mut out: Vec<(String, Vec<Data>)> = Vec::new();
+        for (name, expression) in entries {
+            if let Expression::Word(columnname) = &expression {
+                // Every column is named, unknown ones aren't taken as data.
+                let _ = self.search_for_column(columnname.clone())?;
+            }
+            let mut computed: Vec<Data> = Vec::new();
+            for index in &indicies {
+                computed.push(self.compute(&expression, *index)?);
+            }
+            out.push((name, computed));
+        }
+        return Ok(Success::ResultSet(ResultSet::from(out)));
+    }
+
     /// Writes new data into columns of this table.
+    /// If any unique or check constraint is violated, the row is removed again.
     ///
     /// ## Returns
     /// A generic `SuccessMessage`.
     ///
     /// ## Errors
-    /// If the supplied column doesn't exist.
+    /// If the supplied column doesn't exist, or a constraint is violated.
     pub fn new_data(&mut self, value: Vec<(String, Data)>) -> Result<Success, Error> {
+        let rows = self.len();
+        let answ = self.new_row(value);
+        if answ.is_err() {
+            for column in &mut self.columns {
+                column.contents.all_row_data.truncate(rows);
+                column.rebuild_index();
+            }
+        }
+        return answ;
+    }
+
+    /// Writes many rows of new data into columns of this table at once. With `on conflict`, a row
+    /// whose entry of the unique column exists already, in the table or in an earlier row of the
+    /// same statement, updates that row with its other entries instead, or is left out.
+    /// Every row is checked before any is written, so if one violates a constraint the table stays
+    /// as it was.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the conflict column doesn't exist or isn't unique, or a constraint is violated.
+    pub fn new_data_rows(&mut self, rows: Vec<Vec<(String, Data)>>, on_conflict: Option<(String, OnConflict)>) -> Result<Success, Error> {
+        let conflict = match on_conflict {
+            Some((columnname, action)) => {
+                let column_index = self.search_for_column(columnname.clone())?;
+                if !self.columns[column_index].unique {
+                    return Err(Error::other(format!("Column with name {} is not unique, so nothing can conflict with it.", columnname)));
+                }
+                Some((column_index, action))
+            },
+            None => None,
+        };
+        // Every entry of each new row, and of each existing row that is updated.
+        let mut new_rows: Vec<Vec<Data>> = Vec::new();
+        let mut updated: Vec<(usize, Vec<Data>)> = Vec::new();
+        for value in rows {
+            let mut entries: Vec<Option<Data>> = vec![None; self.columns.len()];
+            // Just like a single row, entries of unknown columns are left out.
+            for (columnname, data) in value {
+                if let Ok(column_index) = self.search_for_column(columnname) {
+                    entries[column_index] = Some(data);
+                }
+            }
+            if let Some((column_index, action)) = &conflict {
+                let key = entries[*column_index].clone().unwrap_or_default();
+                let target = match new_rows.iter().position(|row| row[*column_index] == key) {
+                    Some(position) => Some(&mut new_rows[position]),
+                    None => match self.find_column_data(self.columns[*column_index].name.clone(), &Operator::Equal, &key)?.first() {
+                        Some(row) => {
+                            if !updated.iter().any(|entry| entry.0 == *row) {
+                                updated.push((*row, self.columns.iter().map(|column| column.contents.all_row_data[*row].clone()).collect()));
+                            }
+                            updated.iter_mut().find(|entry| entry.0 == *row).map(|entry| &mut entry.1)
+                        },
+                        None => None,
+                    },
+                };
+                if let Some(target) = target {
+                    // The conflicting entry stays, writing it again would conflict with itself.
+                    if action == &OnConflict::Update {
+                        for (position, data) in entries.into_iter().enumerate() {
+                            if position != *column_index && data.is_some() {
+                                target[position] = data.unwrap();
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+            new_rows.push(entries.into_iter().map(|data| data.unwrap_or_default()).collect());
+        }
+        self.check_new_rows(&new_rows, &updated)?;
+        for (row, entries) in updated {
+            for (column, data) in self.columns.iter_mut().zip(entries) {
+                column.write_data(row, data);
+            }
+        }
+        for entries in new_rows {
+            let row = self.len();
+            for (column, data) in self.columns.iter_mut().zip(entries) {
+                column.write_data(row, data);
+            }
+        }
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Checks the rows before they are written: every unique column stays unique, counting the
+    /// other rows being written, and every row fulfills the check constraints.
+    ///
+    /// ## Errors
+    /// If a constraint would be violated.
+    fn check_new_rows(&self, new_rows: &[Vec<Data>], updated: &[(usize, Vec<Data>)]) -> Result<(), Error> {
+        let updated_rows: HashSet<usize> = updated.iter().map(|entry| entry.0).collect();
+        let all_rows = || updated.iter().map(|entry| &entry.1).chain(new_rows.iter());
+        for (column_index, column) in self.columns.iter().enumerate().filter(|column| column.1.unique) {
+            let mut seen: HashSet<IndexKey> = HashSet::new();
+            for data in all_rows().map(|row| &row[column_index]) {
+                // Rows being updated are replaced by their new entries.
+                let taken = self.find_column_data(column.name.clone(), &Operator::Equal, data)?.iter().any(|row| !updated_rows.contains(row));
+                // A NaN is never equal to another one.
+                if taken || (is_comparable(data) && !seen.insert(IndexKey(data.clone()))) {
+                    return Err(Error::other(format!("This column ({:?}) is marked as unique and {:?} was found to be an entry already.", column.name, data)));
+                }
+            }
+        }
+        for check in &self.checks {
+            for row in all_rows() {
+                let fulfilled = evaluate_conditions(&check.conditions, |name, operator, data| {
+                    return Ok(operator.matches(&row[self.search_for_column(name.to_string())?], data));
+                })?;
+                if !fulfilled {
+                    let values: Vec<(String, Data)> = self.columns.iter().zip(row).map(|(column, data)| (column.name.clone(), data.clone())).collect();
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Check constraint '{}' of table '{}' violated by {:?}, aborting task, no data changed!", check.name, self.name, values)));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Writes the new row, and checks the constraints for it.
+    fn new_row(&mut self, value: Vec<(String, Data)>) -> Result<Success, Error> {
         let name_vec: Vec<String> = value.iter().map(|entry| {entry.0.clone()}).collect();
         for column in &mut self.columns {
             if name_vec.contains(&column.name) {
@@ -162,6 +434,7 @@ impl Table {
                 let _ = column.new_data(Data::Null())?;
             }
         }
+        self.check_constraints(&vec![self.len().saturating_sub(1)])?;
         return Ok(Success::SuccessMessage(true));
     }
 
@@ -191,10 +464,151 @@ impl Table {
     /// ## Errors
     /// If the supplied column doesn't exist.
     pub fn delete_column(&mut self, columnname: String) -> Result<Success, Error> {
+        for check in &self.checks {
+            for condition in &check.conditions {
+                if let Condition::Compare(name, _, _) = condition {
+                    if name == &columnname {
+                        return Err(Error::other(format!("Column '{}' is used by the check constraint '{}', aborting task, no data changed!", columnname, check.name)));
+                    }
+                }
+            }
+        }
         let _ = self.columns.remove(self.search_for_column(columnname)?);
         return Ok(Success::SuccessMessage(true));
     }
 
+    /// Renames the column with the given column name, along with its uses in the check
+    /// constraints and foreign keys of this table.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist, or there is a column with the new name already.
+    pub fn rename_column(&mut self, columnname: String, new_name: String) -> Result<Success, Error> {
+        let column_index = self.search_for_column(columnname.clone())?;
+        if self.search_for_column(new_name.clone()).is_ok() {
+            return Err(Error::other(format!("Column with name '{}' exists already.", new_name)));
+        }
+        self.columns[column_index].name = new_name.clone();
+        for check in &mut self.checks {
+            for condition in &mut check.conditions {
+                if let Condition::Compare(name, _, _) = condition {
+                    if name == &columnname {
+                        *name = new_name.clone();
+                    }
+                }
+            }
+        }
+        let tablename = self.name.clone();
+        self.rename_references(&tablename, Some(&columnname), &new_name);
+        return Ok(Success::SuccessMessage(true));
+    }
+
+    /// Renames the table or column the foreign keys of this table point to. Without a column,
+    /// the table is renamed, otherwise the column of the table.
+    ///
+    /// ## Returns
+    /// `true` if any foreign key was changed.
+    pub fn rename_references(&mut self, tablename: &str, columnname: Option<&str>, new_name: &str) -> bool {
+        let mut out = false;
+        for column in &mut self.columns {
+            if let Some(reference) = &mut column.references {
+                if reference.table != tablename {
+                    continue;
+                }
+                match columnname {
+                    None => reference.table = new_name.to_string(),
+                    Some(columnname) if reference.column == columnname => reference.column = new_name.to_string(),
+                    Some(_) => continue,
+                }
+                out = true;
+            }
+        }
+        return out;
+    }
+
+    /// Returns the names of the other tables the foreign keys of this table point to.
+    pub fn referenced_tables(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for reference in self.columns.iter().filter_map(|column| column.references.as_ref()) {
+            if reference.table != self.name && !out.contains(&reference.table) {
+                out.push(reference.table.clone());
+            }
+        }
+        return out;
+    }
+
+    /// Checks if any foreign key of this table points to the table, or only to the supplied
+    /// column of it.
+    pub fn references(&self, tablename: &str, columnname: Option<&str>) -> bool {
+        return self.columns.iter().filter_map(|column| column.references.as_ref()).any(|reference| {
+            return reference.table == tablename && columnname.is_none_or(|columnname| reference.column == columnname);
+        });
+    }
+
+    /// Creates an index for the column with the given column name.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist.
+    pub fn create_index(&mut self, columnname: String, ordered: bool) -> Result<Success, Error> {
+        let column_index = self.search_for_column(columnname)?;
+        return Ok(self.columns[column_index].create_index(ordered));
+    }
+
+    /// Marks the column with the given column name as unique or not.
+    ///
+    /// ## Returns
+    /// A generic `SuccessMessage`.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist, or should be unique but an entry is in it more than
+    /// once.
+    pub fn set_unique(&mut self, columnname: String, unique: bool) -> Result<Success, Error> {
+        let column_index = self.search_for_column(columnname)?;
+        return self.columns[column_index].set_unique(unique);
+    }
+
+    /// Sorts the supplied rows by the entries of the given column, rows with equal entries keep
+    /// their order. Uses the index of the column if it is ordered.
+    ///
+    /// ## Returns
+    /// The sorted rows.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist.
+    pub fn order_rows(&self, mut indicies: Vec<usize>, columnname: String, descending: bool) -> Result<Vec<usize>, Error> {
+        let column = &self.columns[self.search_for_column(columnname)?];
+        if let Some(sorted) = column.index.as_ref().and_then(|index| index.sorted_rows(descending)) {
+            let mut wanted = vec![0; self.len()];
+            for index in &indicies {
+                wanted[*index] += 1;
+            }
+            let mut out: Vec<usize> = Vec::new();
+            for row in sorted {
+                for _ in 0..wanted[row] {
+                    out.push(row);
+                }
+            }
+            return Ok(out);
+        }
+        let data = &column.contents.all_row_data;
+        indicies.sort_by(|one, two| {
+            match (is_comparable(&data[*one]), is_comparable(&data[*two])) {
+                (true, true) => {
+                    let order = data[*one].partial_cmp(&data[*two]).unwrap_or(Ordering::Equal);
+                    if descending { order.reverse() } else { order }
+                },
+                // Entries that can't be compared, like NaN, come last in either direction.
+                (one, two) => two.cmp(&one),
+            }
+        });
+        return Ok(indicies);
+    }
+
     /// ## Returns
     /// A vector containing the indices of all rows.
     pub fn select_all_rows(&self) -> Vec<usize> {
@@ -207,22 +621,378 @@ impl Table {
         return out;
     }
 
-    /// Returns the index of the data in the column.
-    pub fn search_column_data(&self, columnname: String, data: Data) -> Result<Vec<usize>, Error> {
+    /// Returns the indices of all entries in the column fulfilling the operator with the data.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist, or no entry was found.
+    pub fn search_column_data(&self, columnname: String, operator: &Operator, data: &Data) -> Result<Vec<usize>, Error> {
+        let out = self.find_column_data(columnname, operator, data)?;
+        if out.len() > 0 {
+            return Ok(out);
+        } else {
+            return Err(Error::new(ErrorKind::NotFound, "No data found!"));
+        }
+    }
+
+    /// Chooses how the rows of the column fulfilling the operator with the data are found. An
+    /// index lookup collects and sorts the rows it finds, once more than half of the table is
+    /// expected reading the column is cheaper.
+    pub fn plan_access(&self, column_index: usize, operator: &Operator, data: &Data) -> Access {
+        let access = match (&self.columns[column_index].index, operator) {
+            (Some(Index::Hash(_)), Operator::Equal) => Access::HashLookup,
+            (Some(Index::Hash(_)), Operator::In) if data.is_list() => Access::HashLookup,
+            (Some(Index::Ordered(_)), Operator::Equal) => Access::OrderedLookup,
+            (Some(Index::Ordered(_)), Operator::In) if data.is_list() => Access::OrderedLookup,
+            (Some(Index::Ordered(_)), Operator::Smaller | Operator::SmallerEqual | Operator::Larger | Operator::LargerEqual) => Access::OrderedRange,
+            _ => return Access::FullScan,
+        };
+        if self.estimate_rows(column_index, operator, data) * 2 > self.len() {
+            return Access::FullScan;
+        }
+        return access;
+    }
+
+    /// Returns the indices of all entries in the column fulfilling the operator with the data.
+    /// Finding nothing is not an error.
+    ///
+    /// ## Errors
+    /// If the supplied column doesn't exist.
+    pub fn find_column_data(&self, columnname: String, operator: &Operator, data: &Data) -> Result<Vec<usize>, Error> {
         let column_index = self.search_for_column(columnname)?;
+        let column = &self.columns[column_index];
         let mut out: Vec<usize> = Vec::new();
-        let mut counter: usize = 0;
-        for entry in &self.columns[column_index].contents.all_row_data {
-            if entry == &data {
-                out.push(counter);
+        match self.plan_access(column_index, operator, data) {
+            Access::HashLookup | Access::OrderedLookup => {
+                let index = column.index.as_ref().unwrap();
+                if operator == &Operator::In {
+                    for entry in data.get_list().unwrap() {
+                        out.extend(index.get(&entry));
+                    }
+                    out.sort();
+                    out.dedup();
+                } else {
+                    out = index.get(data);
+                }
+            },
+            Access::OrderedRange => {
+                out = column.index.as_ref().unwrap().range(operator, data).unwrap_or_default();
+            },
+            // Comparisons are never decided by a subquery.
+            Access::FullScan | Access::Subquery => {
+                #[cfg(feature = "parallel")]
+                {
+                    out = parallel::scan(&column.contents.all_row_data, |entry| operator.matches(entry, data));
+                }
+                #[cfg(not(feature = "parallel"))]
+                for (counter, entry) in column.contents.all_row_data.iter().enumerate() {
+                    if operator.matches(entry, data) {
+                        out.push(counter);
+                    }
+                }
+            },
+        }
+        return Ok(out);
+    }
+
+    /// Estimates how many rows fulfill the operator with the data, without reading any of them.
+    /// The index of the column, if there is one, knows how many different entries it holds and
+    /// if it is ordered its smallest and largest entry. For anything else a fixed share of the
+    /// table is assumed.
+    pub fn estimate_rows(&self, column_index: usize, operator: &Operator, data: &Data) -> usize {
+        let rows = self.len();
+        if let Some(index) = &self.columns[column_index].index {
+            match operator {
+                Operator::Equal => return index.estimate_equal(data, rows),
+                Operator::In if data.is_list() => {
+                    let estimate: usize = data.get_list().unwrap().iter().map(|entry| index.estimate_equal(entry, rows)).sum();
+                    return estimate.min(rows);
+                },
+                _ => {
+                    if let Some(share) = index.estimate_range(operator, data) {
+                        return (share * rows as f64).round() as usize;
+                    }
+                },
             }
-            counter += 1;
         }
-        if out.len() > 0 {
-            return Ok(out);
-        } else {
-            return Err(Error::other("No data found!"));
+        let estimate = match operator {
+            Operator::Equal => rows / 10,
+            Operator::NotEqual => rows - rows / 10,
+            Operator::In => rows / 10 * data.get_list().map(|list| list.len()).unwrap_or(1),
+            _ => rows / 3,
+        };
+        return estimate.clamp(rows.min(1), rows);
+    }
+
+    /// Combines the rows expected so far with the estimate of the next comparison, as if the
+    /// rows they find were independent of each other.
+    ///
+    /// ## Errors
+    /// If the connector isn't one of and / not / or / xor.
+    fn combine_estimates(&self, expected: usize, connector: Option<&str>, estimate: usize) -> Result<usize, Error> {
+        let rows = self.len() as f64;
+        if connector.is_none() || rows == 0.0 {
+            return Ok(estimate);
         }
+        let found = expected as f64 / rows;
+        let other = estimate as f64 / rows;
+        let share = match connector.unwrap() {
+            "and" => found * other,
+            "not" => found * (1.0 - other),
+            "or" => found + other - found * other,
+            "xor" => found + other - 2.0 * found * other,
+            _ => return Err(Error::other("Invalid nql syntax.")),
+        };
+        return Ok((share * rows).round() as usize);
+    }
+
+    /// Describes how a decoded condition list is evaluated, one step per comparison in the order
+    /// they are evaluated.
+    ///
+    /// ## Returns
+    /// A vector containing the touple [`(String, Access, usize)`] of the step, how its rows are
+    /// found and how many rows are expected after it.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or the list is not alternating between comparisons and
+    /// connectors.
+    pub fn explain_conditions(&self, conditions: &[Condition]) -> Result<Vec<(String, Access, usize)>, Error> {
+        let mut out: Vec<(String, Access, usize)> = Vec::new();
+        let mut expected: usize = 0;
+        let mut read_condition: Option<String> = None;
+        for (position, condition) in conditions.iter().enumerate() {
+            match (condition, position == 0, read_condition.clone()) {
+                (Condition::Compare(name, operator, data), first, connector) if first == connector.is_none() => {
+                    let column_index = self.search_for_column(name.to_string())?;
+                    let access = self.plan_access(column_index, operator, data);
+                    let estimate = self.estimate_rows(column_index, operator, data);
+                    let mut step = format!("{} {} {}", name, operator.to_nql(), decode_data_to_jsonval(data.clone()).dump());
+                    expected = self.combine_estimates(expected, connector.as_deref(), estimate)?;
+                    if let Some(connector) = connector {
+                        step = format!("{} {}", connector, step);
+                    }
+                    out.push((step, access, expected));
+                    read_condition = None;
+                },
+                (Condition::Exists(exists), first, connector) if first == connector.is_none() => {
+                    let estimate = if *exists { self.len() } else { 0 };
+                    let mut step = format!("exists {}", exists);
+                    expected = self.combine_estimates(expected, connector.as_deref(), estimate)?;
+                    if let Some(connector) = connector {
+                        step = format!("{} {}", connector, step);
+                    }
+                    out.push((step, Access::Subquery, expected));
+                    read_condition = None;
+                },
+                (Condition::Connector(connector), false, None) => {
+                    read_condition = Some(connector.to_string());
+                },
+                _ => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+            }
+        }
+        if conditions.is_empty() || read_condition.is_some() {
+            return Err(Error::other("Invalid nql syntax."));
+        }
+        return Ok(out);
+    }
+
+    /// Evaluates a decoded condition list from left to right, e.g.
+    /// `[column = data,+ and other_column > other_data,+ ...]`
+    ///
+    /// ## Returns
+    /// The indices of all rows fulfilling the conditions.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, the list is not alternating between comparisons and connectors,
+    /// or a single comparison found no data.
+    pub fn search_conditions(&self, conditions: &[Condition]) -> Result<Vec<usize>, Error> {
+        let mut found_data: Vec<usize> = Vec::new();
+        let mut read_condition: Option<String> = None;
+        let mut searches = self.search_comparisons(conditions).into_iter();
+        for (position, condition) in conditions.iter().enumerate() {
+            match (condition, position == 0, read_condition.clone()) {
+                (Condition::Connector(connector), false, None) => {
+                    read_condition = Some(connector.to_string());
+                },
+                (Condition::Connector(_), _, _) => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+                (_, true, None) => {
+                    found_data = searches.next().unwrap()?;
+                },
+                (_, false, Some(connector)) => {
+                    let other_search = searches.next().unwrap()?;
+                    found_data = condition_check(found_data, connector, other_search)?;
+                    read_condition = None;
+                },
+                _ => return Err(Error::other(format!("Invalid nql syntax: {:?} is out of place.", condition))),
+            }
+        }
+        if conditions.is_empty() || read_condition.is_some() {
+            return Err(Error::other("Invalid nql syntax."));
+        }
+        return Ok(found_data);
+    }
+
+    /// Searches the column of every comparison in the condition list, in the order they are
+    /// written. An `exists` finds every row or none.
+    fn search_comparisons(&self, conditions: &[Condition]) -> Vec<Result<Vec<usize>, Error>> {
+        #[cfg(feature = "parallel")]
+        if self.len() >= parallel::MIN_CHUNK_SIZE {
+            return self.search_comparisons_parallel(conditions);
+        }
+        let mut out: Vec<Result<Vec<usize>, Error>> = Vec::new();
+        for condition in conditions {
+            match condition {
+                Condition::Compare(name, operator, data) => out.push(self.search_column_data(name.to_string(), operator, data)),
+                Condition::Exists(true) => out.push(Ok(self.select_all_rows())),
+                Condition::Exists(false) => out.push(Ok(Vec::new())),
+                Condition::Connector(_) => {},
+            }
+        }
+        return out;
+    }
+
+    /// Searches the column of every comparison in the condition list at the same time, each on
+    /// its own thread. The results are in the order the comparisons are written. An `exists`
+    /// finds every row or none.
+    #[cfg(feature = "parallel")]
+    fn search_comparisons_parallel(&self, conditions: &[Condition]) -> Vec<Result<Vec<usize>, Error>> {
+        return std::thread::scope(|scope| {
+            let mut threads = Vec::new();
+            for condition in conditions {
+                match condition {
+                    Condition::Compare(name, operator, data) => threads.push(scope.spawn(move || self.search_column_data(name.to_string(), operator, data))),
+                    Condition::Exists(exists) => threads.push(scope.spawn(move || Ok(if *exists { self.select_all_rows() } else { Vec::new() }))),
+                    Condition::Connector(_) => {},
+                }
+            }
+            return threads.into_iter().map(|search| search.join().unwrap()).collect();
+        });
+    }
+
+    /// Evaluates a decoded condition list from left to right for a single row.
+    ///
+    /// ## Returns
+    /// `true` if the row fulfills the conditions, `false` otherwise.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or the list is not alternating between comparisons and
+    /// connectors.
+    pub fn matches_conditions(&self, row: usize, conditions: &[Condition]) -> Result<bool, Error> {
+        return evaluate_conditions(conditions, |name, operator, data| {
+            let column_index = self.search_for_column(name.to_string())?;
+            return Ok(operator.matches(&self.columns[column_index].contents.all_row_data[row], data));
+        });
+    }
+
+    /// Checks the supplied rows against every check constraint of the table.
+    ///
+    /// ## Errors
+    /// An `InvalidData` error naming the violated check.
+    pub fn check_constraints(&self, rows: &Vec<usize>) -> Result<(), Error> {
+        for check in &self.checks {
+            for row in rows {
+                if !self.matches_conditions(*row, &check.conditions)? {
+                    let values: Vec<(String, Data)> = self.columns.iter().map(|column| (column.name.clone(), column.contents.all_row_data[*row].clone())).collect();
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Check constraint '{}' of table '{}' violated by {:?}, aborting task, no data changed!", check.name, self.name, values)));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Checks if setting the columns of the rows to `Null`, one column after the other, would
+    /// violate a unique or check constraint. Nothing is changed.
+    ///
+    /// ## Errors
+    /// If a column doesn't exist, or a constraint would be violated.
+    pub fn check_set_null(&self, updates: &[(String, Vec<usize>)]) -> Result<(), Error> {
+        let null = Data::Null();
+        // Every entry set to null so far, as the column index and row.
+        let mut nulled: HashSet<(usize, usize)> = HashSet::new();
+        for (columnname, rows) in updates {
+            let column_index = self.search_for_column(columnname.clone())?;
+            let column = &self.columns[column_index];
+            let null_written = column.contains(&null) || nulled.iter().any(|entry| entry.0 == column_index);
+            if column.unique && (null_written || rows.len() > 1) {
+                return Err(Error::other(format!("This column ({:?}) is marked as unique and {:?} was found to be an entry already.", column.name, null)));
+            }
+            nulled.extend(rows.iter().map(|row| (column_index, *row)));
+            for check in &self.checks {
+                for row in rows {
+                    let fulfilled = evaluate_conditions(&check.conditions, |name, operator, data| {
+                        let index = self.search_for_column(name.to_string())?;
+                        let entry = if nulled.contains(&(index, *row)) { &null } else { &self.columns[index].contents.all_row_data[*row] };
+                        return Ok(operator.matches(entry, data));
+                    })?;
+                    if !fulfilled {
+                        return Err(Error::new(ErrorKind::InvalidData, format!("Check constraint '{}' of table '{}' violated by setting {} to {:?}, aborting task, no data changed!", check.name, self.name, columnname, null)));
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Checks the table for damage: columns of different lengths, duplicates in unique columns,
+    /// indexes out of step with their column, rows violating a check constraint, and, with
+    /// `json`, entries that can't be saved as json.
+    ///
+    /// ## Returns
+    /// A vector containing the touple [`(String, bool, String)`] of the name of each check, if
+    /// it passed, and what was found if not.
+    pub fn verify(&self, json: bool) -> Vec<(String, bool, String)> {
+        /// Json has no NaN or infinity, and Neith saves lists nested up to 5 times.
+        fn json_compatible(data: &Data, depth: usize) -> bool {
+            match data {
+                Data::Float(float) => return float.is_finite(),
+                Data::List(list) => return depth < 5 && list.iter().all(|entry| json_compatible(entry, depth + 1)),
+                _ => return true,
+            }
+        }
+        let mut out: Vec<(String, bool, String)> = Vec::new();
+        let rows = self.len();
+        let uneven: Vec<String> = self.columns.iter().filter(|column| column.contents.all_row_data.len() != rows).map(|column| format!("{} has {} rows instead of {}", column.name, column.contents.all_row_data.len(), rows)).collect();
+        out.push(("column lengths".to_string(), uneven.is_empty(), uneven.join(", ")));
+        let mut duplicates: Vec<String> = Vec::new();
+        for column in self.columns.iter().filter(|column| column.unique) {
+            let mut seen: HashSet<IndexKey> = HashSet::new();
+            for data in column.contents.all_row_data.iter().filter(|data| is_comparable(data)) {
+                if !seen.insert(IndexKey(data.clone())) {
+                    duplicates.push(format!("{:?} is in {} more than once", data, column.name));
+                }
+            }
+        }
+        out.push(("unique".to_string(), duplicates.is_empty(), duplicates.join(", ")));
+        let mut stale: Vec<String> = Vec::new();
+        for column in &self.columns {
+            if let Some(index) = &column.index {
+                let all_row_data = &column.contents.all_row_data;
+                if index.row_count() != all_row_data.len() || !(0..all_row_data.len()).all(|row| index.points_to(&all_row_data[row], row)) {
+                    stale.push(format!("the index of {} does not match its entries", column.name));
+                }
+            }
+        }
+        out.push(("index".to_string(), stale.is_empty(), stale.join(", ")));
+        if uneven.is_empty() {
+            let mut violated: Vec<String> = Vec::new();
+            for check in &self.checks {
+                let failing = (0..rows).filter(|row| !self.matches_conditions(*row, &check.conditions).unwrap_or(false)).count();
+                if failing > 0 {
+                    violated.push(format!("{} is violated by {} rows", check.name, failing));
+                }
+            }
+            out.push(("check constraints".to_string(), violated.is_empty(), violated.join(", ")));
+        }
+        if json {
+            let mut incompatible: Vec<String> = Vec::new();
+            for column in &self.columns {
+                let found = column.contents.all_row_data.iter().filter(|data| !json_compatible(data, 0)).count();
+                if found > 0 {
+                    incompatible.push(format!("{} has {} entries json can't hold", column.name, found));
+                }
+            }
+            out.push(("types".to_string(), incompatible.is_empty(), incompatible.join(", ")));
+        }
+        return out;
     }
 
     /// Gives the number of rows in the table.
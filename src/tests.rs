@@ -196,3 +196,961 @@ fn test_alt_connection() {
     assert_eq!(del_data, del_column);
     assert_eq!(del_column, del_table);
 }
+
+#[test]
+fn test_foreign_keys() {
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table parents with (id true, name false)").unwrap();
+    let _ = con.execute("new table children with (id true, parent false references parents(id) on delete cascade, name false)").unwrap();
+    let _ = con.execute("new table toys with (id true, owner false references children(id) on delete set null)").unwrap();
+    let _ = con.execute("new table schools with (id true, pupil false references children(id))").unwrap();
+    assert!(con.execute("new table broken with (id true, parent false references nothing(id))").is_err());
+    // A parenthesis has to be closed, it never becomes part of a name
+    assert!(con.execute("new column parents with (nickname false").is_err());
+    assert!(con.execute("new column parents with nickname false)").is_err());
+    assert!(con.execute("select (id, name from parents").is_err());
+    for id in 0..3 {
+        let _ = con.execute(format!("new data parents (id = {id},+ name = parent{id})").as_str()).unwrap();
+        let _ = con.execute(format!("new data children (id = {id},+ parent = {id},+ name = child{id})").as_str()).unwrap();
+        let _ = con.execute(format!("new data toys (id = {id},+ owner = {id})").as_str()).unwrap();
+    }
+    // Parent 5 does not exist
+    assert!(con.execute("new data children (id = 5,+ parent = 5,+ name = orphan)").is_err());
+    assert!(con.execute("update children where [id = 0] with (parent = 5)").is_err());
+    // Cascades to children, and sets the owner of their toys to null
+    let _ = con.execute("delete data in parents where [id = 0]").unwrap();
+    let children = con.execute("get len of children").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(children, 2.0);
+    let owners = con.execute("select (owner) from toys where [id = 0]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert!(owners[0].is_null());
+    // Restricted by the school
+    let _ = con.execute("new data schools (id = 0,+ pupil = 1)").unwrap();
+    assert!(con.execute("delete data in parents where [id = 1]").is_err());
+    let parents = con.execute("get len of parents").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(parents, 2.0);
+    // Setting the badge to null violates its check, so neither the toys nor the children change
+    let _ = con.execute("new table badges with (id true, owner false references children(id) on delete set null) check [owner >= 0]").unwrap();
+    let _ = con.execute("new data badges (id = 0,+ owner = 2)").unwrap();
+    assert!(con.execute("delete data in parents where [id = 2]").is_err());
+    let owners = con.execute("select (owner) from toys where [id = 2]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(owners[0].get_float().unwrap(), 2.0);
+    assert_eq!(con.execute("get len of children").unwrap().get_result().unwrap()[0].get_float().unwrap(), 2.0);
+    assert!(con.execute("delete table with children").is_err());
+    // A foreign key added with a new column is followed just the same
+    let _ = con.execute("new table leaders with (id true)").unwrap();
+    let _ = con.execute("new table clubs with (id true)").unwrap();
+    let _ = con.execute("new column clubs with (leader false references leaders(id))").unwrap();
+    let _ = con.execute("new data leaders (id = 0)").unwrap();
+    let _ = con.execute("new data clubs (id = 0,+ leader = 0)").unwrap();
+    assert!(con.execute("delete data in leaders where [id = 0]").is_err());
+    assert!(con.execute("truncate leaders").is_err());
+}
+
+#[test]
+fn test_check_constraints() {
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table products with (id true, price false, status false) check positive_price [price >= 0] check [status in (open, closed)]").unwrap();
+    let _ = con.execute("new data products (id = 0,+ price = 10,+ status = open)").unwrap();
+    let _ = con.execute("new data products (id = 1,+ price = 2.5,+ status = closed)").unwrap();
+    let negative = con.execute("new data products (id = 2,+ price = -1,+ status = open)").unwrap_err();
+    assert_eq!(negative.kind(), std::io::ErrorKind::InvalidData);
+    assert!(negative.to_string().contains("positive_price"));
+    let status = con.execute("update products where [id = 0] with (status = lost)").unwrap_err();
+    assert!(status.to_string().contains("products_check_1"));
+    // Nothing was written by the failed statements
+    let len = con.execute("get len of products").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(len, 2.0);
+    let statuses = con.execute("select (status) from products where [id = 0]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(statuses[0].get_string().unwrap(), "open".to_string());
+    // Range conditions in where-clauses
+    let cheap = con.execute("select (id) from products where [price < 5,+ or status != open]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(cheap.len(), 1);
+    assert!(con.execute("delete column with price in products").is_err());
+    assert!(con.execute("new table broken with (id true) check [price >= 0]").is_err());
+}
+
+#[test]
+fn test_hash_index() {
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table indexed with (id true, group false)").unwrap();
+    let _ = con.execute("create index on indexed(group)").unwrap();
+    assert!(con.execute("create index on indexed(nothing)").is_err());
+    for id in 0..100 {
+        let group = id % 4;
+        let _ = con.execute(format!("new data indexed (id = {id},+ group = {group})").as_str()).unwrap();
+    }
+    assert!(con.execute("new data indexed (id = 5,+ group = 1)").is_err());
+    let _ = con.execute("delete data in indexed where [group = 0]").unwrap();
+    let _ = con.execute("update indexed where [id = 1] with (group = 3)").unwrap();
+    let ids = con.execute("select (id) from indexed where [group = 1]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(ids.len(), 24);
+    assert_eq!(ids[0].get_float().unwrap(), 5.0);
+    let moved = con.execute("select (id) from indexed where [group in (3, 4),+ and id = 1]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(moved.len(), 1);
+    let found = con.execute("select (group) from indexed where [id = 99]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(found[0].get_float().unwrap(), 3.0);
+    // Freed up by the deletion
+    let _ = con.execute("new data indexed (id = 0,+ group = 0)").unwrap();
+}
+
+#[test]
+fn test_ordered_index() {
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table ordered with (id true, price false, name false)").unwrap();
+    let prices = [5, 3, 9, 3, 1, 7];
+    for (id, price) in prices.iter().enumerate() {
+        let _ = con.execute(format!("new data ordered (id = {id},+ price = {price},+ name = item{id})").as_str()).unwrap();
+    }
+    // Same answers without and with the index
+    let unindexed = con.execute("select (id) from ordered where [price >= 3,+ and price < 9] order by price desc").unwrap();
+    let _ = con.execute("create index on ordered(price) ordered").unwrap();
+    let indexed = con.execute("select (id) from ordered where [price >= 3,+ and price < 9] order by price desc").unwrap();
+    assert_eq!(unindexed, indexed);
+    let ids: Vec<f64> = indexed.get_result().unwrap()[0].get_list().unwrap().iter().map(|id| id.get_float().unwrap()).collect();
+    assert_eq!(ids, vec![5.0, 0.0, 1.0, 3.0]);
+    let _ = con.execute("delete data in ordered where [price = 1]").unwrap();
+    let _ = con.execute("update ordered where [id = 2] with (price = 0.5)").unwrap();
+    let min = con.execute("get min in price from ordered").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(min, 0.5);
+    let max = con.execute("get max in price from ordered").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(max, 7.0);
+    let names = con.execute("select (name) from ordered order by price").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(names[0].get_string().unwrap(), "item2".to_string());
+    assert_eq!(names.len(), 5);
+    // A NaN is neither equal to, smaller nor larger than anything, with or without the index
+    let _ = con.execute("new data ordered (id = 6,+ price = NaN,+ name = unknown)").unwrap();
+    let larger = con.execute("select (id) from ordered where [price > 5]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(larger.len(), 1);
+    assert!(con.execute("select (id) from ordered where [price = NaN]").is_err());
+    let _ = con.execute("create index on ordered(name)").unwrap();
+    assert!(con.execute("select (id) from ordered where [name = unknown,+ and price = NaN]").is_err());
+    // Min, max and sorting skip a NaN just the same, with or without the index
+    for table in ["scanned", "sorted"] {
+        let _ = con.execute(format!("new table {table} with (id true, price false)").as_str()).unwrap();
+        for (id, price) in ["NaN", "2", "NaN", "8", "-1"].iter().enumerate() {
+            let _ = con.execute(format!("new data {table} (id = {id},+ price = {price})").as_str()).unwrap();
+        }
+    }
+    let _ = con.execute("create index on sorted(price) ordered").unwrap();
+    for table in ["scanned", "sorted"] {
+        let min = con.execute(format!("get min in price from {table}").as_str()).unwrap().get_result().unwrap()[0].get_float().unwrap();
+        assert_eq!(min, -1.0);
+        let max = con.execute(format!("get max in price from {table}").as_str()).unwrap().get_result().unwrap()[0].get_float().unwrap();
+        assert_eq!(max, 8.0);
+        for (order, expected) in [("", vec![4.0, 1.0, 3.0, 0.0, 2.0]), (" desc", vec![3.0, 1.0, 4.0, 0.0, 2.0])] {
+            let ids: Vec<f64> = con.execute(format!("select (id) from {table} order by price{order}").as_str()).unwrap().get_result().unwrap()[0].get_list().unwrap().iter().map(|id| id.get_float().unwrap()).collect();
+            assert_eq!(ids, expected);
+        }
+    }
+}
+
+#[test]
+fn test_explain() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table plans with (id true, price false)").unwrap();
+    for id in 0..20 {
+        let _ = con.execute(format!("new data plans (id = {id},+ price = {})", id % 4).as_str()).unwrap();
+    }
+    let plan = con.execute("explain select (id) from plans where [id = 3,+ and price > 1] order by price desc").unwrap();
+    assert_eq!(plan.get_column_names().unwrap(), vec!["step", "operation", "access", "estimated_rows"]);
+    let columns = plan.get_result().unwrap();
+    let operations = columns[1].get_list().unwrap();
+    let access = columns[2].get_list().unwrap();
+    let estimates = columns[3].get_list().unwrap();
+    assert_eq!(operations.len(), 3);
+    assert_eq!(operations[0].get_string().unwrap(), "filter id = 3");
+    assert_eq!(access[0].get_string().unwrap(), "hash index lookup");
+    assert_eq!(estimates[0].get_float().unwrap(), 1.0);
+    assert_eq!(access[1].get_string().unwrap(), "full scan");
+    assert_eq!(access[2].get_string().unwrap(), "sort");
+    let _ = con.execute("create index on plans(price) ordered").unwrap();
+    // Prices are 0 to 3, so a third of the table is expected above 2
+    let plan = con.execute("explain select (id) from plans where [price > 2] order by price").unwrap().get_result().unwrap();
+    assert_eq!(plan[2].get_list().unwrap()[0].get_string().unwrap(), "ordered index range");
+    assert_eq!(plan[3].get_list().unwrap()[0].get_float().unwrap(), 7.0);
+    assert_eq!(plan[2].get_list().unwrap()[1].get_string().unwrap(), "ordered index scan");
+    // Most of the table is read faster without the index, the answer stays the same
+    let plan = con.execute("explain select (id) from plans where [price >= 1,+ or id = 0]").unwrap().get_result().unwrap();
+    assert_eq!(plan[2].get_list().unwrap(), vec![Data::String("full scan".to_string()), Data::String("hash index lookup".to_string())]);
+    assert_eq!(plan[3].get_list().unwrap(), vec![Data::Float(13.0), Data::Float(13.0)]);
+    let found = con.execute("select (id) from plans where [price >= 1,+ or id = 0]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(found.len(), 16);
+    let scan = con.execute("explain select * from plans").unwrap().get_result().unwrap();
+    assert_eq!(scan[2].get_list().unwrap()[0].get_string().unwrap(), "full scan");
+    assert_eq!(scan[3].get_list().unwrap()[0].get_float().unwrap(), 20.0);
+}
+
+#[test]
+fn test_shared_handle() {
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<Neith>();
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table shared with (id true, worker false)").unwrap();
+    let shared = std::sync::Arc::new(con.clone());
+    let mut workers = Vec::new();
+    for worker in 0..4 {
+        let handle = shared.clone();
+        workers.push(std::thread::spawn(move || {
+            for row in 0..25 {
+                let id = worker * 25 + row;
+                let _ = handle.execute(format!("new data shared (id = {id},+ worker = {worker})").as_str()).unwrap();
+                let _ = handle.execute("select (id) from shared where [worker = 0]").unwrap();
+            }
+        }));
+    }
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let len = con.execute("get len of shared").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(len, 100.0);
+    // Tables created through one handle exist in every other
+    let _ = shared.execute("new table later with (id true)").unwrap();
+    assert!(con.exists_table("later".to_string()).unwrap());
+    // Copying to the same name from many handles at once creates it exactly once
+    let mut creators = Vec::new();
+    for _ in 0..4 {
+        let handle = shared.clone();
+        creators.push(std::thread::spawn(move || handle.execute("copy table shared to twin").is_ok()));
+    }
+    let created = creators.into_iter().map(|creator| creator.join().unwrap()).filter(|created| *created).count();
+    assert_eq!(created, 1);
+    assert_eq!(con.schema().unwrap().iter().filter(|table| table.name == "twin").count(), 1);
+    // Deleting a table created before doesn't move a statement onto another table
+    let deleter = {
+        let handle = shared.clone();
+        std::thread::spawn(move || handle.execute("delete table with later").unwrap())
+    };
+    let _ = con.execute("delete data in twin where [worker = 0]").unwrap();
+    let _ = deleter.join().unwrap();
+    // Every update reads the row it writes, none of them is lost
+    let _ = con.execute("new table counter with (id true, count false)").unwrap();
+    let _ = con.execute("new data counter (id = 0,+ count = 0)").unwrap();
+    let mut incrementers = Vec::new();
+    for _ in 0..8 {
+        let handle = shared.clone();
+        incrementers.push(std::thread::spawn(move || {
+            for _ in 0..50 {
+                let _ = handle.execute("update counter where [id = 0] with (count = count + 1)").unwrap();
+            }
+        }));
+    }
+    for incrementer in incrementers {
+        incrementer.join().unwrap();
+    }
+    assert_eq!(con.execute("get max in count from counter").unwrap().get_result().unwrap()[0].get_float().unwrap(), 400.0);
+    assert_eq!(con.execute("get len of twin").unwrap().get_result().unwrap()[0].get_float().unwrap(), 75.0);
+    assert_eq!(con.execute("get len of shared").unwrap().get_result().unwrap()[0].get_float().unwrap(), 100.0);
+    // Deleting only locks the tables that could point to the deleted rows
+    let busy = con.find_table("counter").unwrap();
+    let guard = busy.write().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let handle = shared.clone();
+    let deleter = std::thread::spawn(move || sender.send(handle.execute("delete data in twin where [worker = 1]").is_ok()).unwrap());
+    assert_eq!(receiver.recv_timeout(std::time::Duration::from_secs(10)), Ok(true));
+    drop(guard);
+    deleter.join().unwrap();
+    assert_eq!(con.execute("get len of twin").unwrap().get_result().unwrap()[0].get_float().unwrap(), 50.0);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel() {
+    use crate::{parallel, data::Data, condition::Operator, column::ColumnData};
+    let mut all_row_data: Vec<Data> = (0..5000).map(|row| Data::Float(((row * 7919) % 1000) as f64)).collect();
+    all_row_data[1234] = Data::String("text".to_string());
+    all_row_data[4321] = Data::Null();
+    let sequential: Vec<usize> = (0..all_row_data.len()).filter(|row| Operator::LargerEqual.matches(&all_row_data[*row], &Data::Float(500.0))).collect();
+    for size in [97, 1024, 5000, 9000] {
+        assert_eq!(parallel::scan_chunks(&all_row_data, size, |entry| Operator::LargerEqual.matches(entry, &Data::Float(500.0))), sequential);
+    }
+    let contents = ColumnData { all_row_data: all_row_data.clone() };
+    // A NaN in the column is skipped, wherever it is, and the first entry still has to win the same way
+    for nan_row in [None, Some(0), Some(2500)] {
+        let mut with_nan = all_row_data.clone();
+        if let Some(row) = nan_row {
+            with_nan[row] = Data::Float(f64::NAN);
+        }
+        // Reading one row after the other, without any threads, skipping the NaN
+        let sequential = |largest: bool| {
+            let mut comparable = with_nan.iter().filter(|data| data.partial_cmp(data).is_some());
+            let mut out = comparable.next().unwrap();
+            for data in comparable {
+                if (largest && data > out) || (!largest && data < out) {
+                    out = data;
+                }
+            }
+            return format!("{:?}", out);
+        };
+        for size in [97, 1000, 4999] {
+            assert_eq!(parallel::min_max_chunks(&with_nan, size, false).map(|min| format!("{:?}", min)), Some(sequential(false)));
+            assert_eq!(parallel::min_max_chunks(&with_nan, size, true).map(|max| format!("{:?}", max)), Some(sequential(true)));
+        }
+    }
+    assert_eq!(contents.max().get_result().unwrap()[0], Data::Null());
+    // Condition lists give the same answer with and without the feature
+    // Small tables are searched on the calling thread, large ones are split up.
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table parallel with (id true, size false)").unwrap();
+    for rows in [50, 2000] {
+        let _ = con.execute("truncate parallel").unwrap();
+        let new_rows: Vec<Vec<(String, Data)>> = (0..rows).map(|id| vec![("id".to_string(), Data::Float(id as f64)), ("size".to_string(), Data::Float((id % 5) as f64))]).collect();
+        let _ = con.insert_many("parallel", new_rows).unwrap();
+        let ids = con.execute(format!("select (id) from parallel where [size > 2,+ and id < 20,+ or id = {}]", rows - 1).as_str()).unwrap().get_result().unwrap()[0].get_list().unwrap();
+        assert_eq!(ids.len(), 9);
+    }
+}
+
+#[test]
+fn test_autosave() {
+    let path = std::env::temp_dir().join("neith_test_autosave.neithdb");
+    let _ = std::fs::remove_file(&path);
+    let mut con = Neith::connect(path.clone());
+    let _ = con.set_autosave(true).unwrap();
+    let _ = con.execute("new table saved with (id true, name false)").unwrap();
+    let _ = con.execute("new data saved (id = 1,+ name = first)").unwrap();
+    let _ = con.execute("update saved where [id = 1] with (name = changed)").unwrap();
+    let reopened = Neith::connect(path.clone());
+    let name = reopened.execute("select (name) from saved").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(name, vec![crate::data::Data::String("changed".to_string())]);
+    // Saving only needs a reference
+    let _ = con.execute("new data saved (id = 2,+ name = second)").unwrap();
+    let _ = con.set_autosave(false).unwrap();
+    let _ = con.execute("new data saved (id = 3,+ name = third)").unwrap();
+    let _ = con.save().unwrap();
+    let _ = con.execute("new data saved (id = 4,+ name = fourth)").unwrap();
+    let reopened = Neith::connect(path.clone());
+    let len = reopened.execute("get len of saved").unwrap().get_result().unwrap()[0].get_float().unwrap();
+    assert_eq!(len, 3.0);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_save_changed_tables() {
+    use crate::StorageLayout;
+    let path = std::env::temp_dir().join("neith_test_changed_tables.neithdb");
+    let files = |path: &std::path::PathBuf| -> std::collections::HashMap<String, Vec<u8>> {
+        return std::fs::read_dir(path).unwrap().map(|entry| {
+            let entry = entry.unwrap();
+            return (entry.file_name().to_string_lossy().to_string(), std::fs::read(entry.path()).unwrap());
+        }).collect();
+    };
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    let mut con = Neith::connect(path.clone());
+    let _ = con.execute("new table kept with (id true)").unwrap();
+    let _ = con.execute("new table changed with (id true)").unwrap();
+    let _ = con.execute("new data kept (id = 1)").unwrap();
+    let _ = con.execute("new data changed (id = 1)").unwrap();
+    let _ = con.save().unwrap();
+    assert!(path.is_file());
+    // Converting replaces the single file with a directory
+    let _ = con.set_storage_layout(StorageLayout::TablePerFile).unwrap();
+    assert!(path.is_dir());
+    assert!(!path.with_extension("neithdb.tmp").exists());
+    let saved = files(&path);
+    assert_eq!(saved.len(), 3);
+    // Reading doesn't count as a change
+    let _ = con.execute("select * from kept").unwrap();
+    let _ = con.execute("get len of kept").unwrap();
+    // Neither does a failed statement
+    assert!(con.execute("new data kept (id = 1)").is_err());
+    let _ = con.execute("update changed where [id = 1] with (id = 2)").unwrap();
+    let _ = con.save().unwrap();
+    let resaved = files(&path);
+    assert_eq!(resaved.len(), 3);
+    let untouched: Vec<&String> = resaved.keys().filter(|file| saved.get(*file) == resaved.get(*file)).collect();
+    assert_eq!(untouched.len(), 1);
+    assert!(String::from_utf8_lossy(&resaved[untouched[0]]).contains("kept"));
+    let _ = con.execute("delete table with kept").unwrap();
+    let _ = con.execute("new table kept with (name false)").unwrap();
+    let _ = con.save().unwrap();
+    drop(con);
+    let mut reopened = Neith::connect(path.clone());
+    assert_eq!(reopened.get_storage_layout(), StorageLayout::TablePerFile);
+    assert!(reopened.execute("select (name) from kept").is_ok());
+    let id = reopened.execute("select (id) from changed").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(id[0].get_float().unwrap(), 2.0);
+    assert!(reopened.verify().unwrap().get_result().unwrap()[2].get_list().unwrap().iter().all(|passed| passed.get_bool().unwrap()));
+    // And back to a single file
+    let _ = reopened.set_storage_layout(StorageLayout::SingleFile).unwrap();
+    // Unchanged tables keep their encoding, changed ones are encoded again
+    let _ = reopened.execute("update changed where [id = 2] with (id = 3)").unwrap();
+    let _ = reopened.save().unwrap();
+    drop(reopened);
+    assert!(path.is_file());
+    let reopened = Neith::connect(path.clone());
+    let id = reopened.execute("select (id) from changed").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(id[0].get_float().unwrap(), 3.0);
+    assert!(reopened.execute("select (name) from kept").is_ok());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_autosave_policy() {
+    use crate::AutosavePolicy;
+    let path = std::env::temp_dir().join("neith_test_autosave_policy.neithdb");
+    let saved_len = |path: &std::path::PathBuf| -> Option<f64> {
+        let reopened = Neith::connect(path.clone());
+        return reopened.execute("get len of policy").ok().map(|len| len.get_result().unwrap()[0].get_float().unwrap());
+    };
+    let _ = std::fs::remove_file(&path);
+    let mut con = Neith::connect(path.clone());
+    let _ = con.set_autosave_policy(AutosavePolicy::EveryNStatements(3)).unwrap();
+    let _ = con.execute("new table policy with (id true)").unwrap();
+    let _ = con.execute("new data policy (id = 1)").unwrap();
+    assert!(!path.exists());
+    let _ = con.execute("new data policy (id = 2)").unwrap();
+    assert_eq!(saved_len(&path), Some(2.0));
+    // Reading data never counts, not even a long run of it
+    let _ = std::fs::remove_file(&path);
+    for _ in 0..4 {
+        let _ = con.execute("select * from policy").unwrap();
+        let _ = con.execute("get len of policy").unwrap();
+        let _ = con.execute("describe policy").unwrap();
+    }
+    assert!(!path.exists());
+    let _ = con.save().unwrap();
+    let _ = con.set_autosave_policy(AutosavePolicy::Interval(std::time::Duration::from_secs(3600))).unwrap();
+    let _ = con.execute("new data policy (id = 3)").unwrap();
+    assert_eq!(saved_len(&path), Some(2.0));
+    let _ = con.set_autosave_policy(AutosavePolicy::OnDrop).unwrap();
+    let handle = con.clone();
+    let _ = handle.execute("new data policy (id = 4)").unwrap();
+    drop(handle);
+    assert_eq!(saved_len(&path), Some(2.0));
+    drop(con);
+    assert_eq!(saved_len(&path), Some(4.0));
+    // The policy is shared, and handles dropped on many threads at once still save after the last
+    let mut con = Neith::connect(path.clone());
+    let _ = con.set_autosave_policy(AutosavePolicy::OnDrop).unwrap();
+    let _ = con.execute("new data policy (id = 5)").unwrap();
+    let handles: Vec<Neith> = (0..8).map(|_| con.clone()).collect();
+    drop(con);
+    let droppers: Vec<std::thread::JoinHandle<()>> = handles.into_iter().map(|handle| std::thread::spawn(move || drop(handle))).collect();
+    for dropper in droppers {
+        dropper.join().unwrap();
+    }
+    assert_eq!(saved_len(&path), Some(5.0));
+    let _ = std::fs::remove_file(&path);
+    assert!(Neith::connect_ram_mode(false).set_autosave_policy(AutosavePolicy::EveryStatement).is_err());
+}
+
+#[test]
+fn test_binary_format() {
+    use crate::{StorageFormat, data::Data, utils::binard};
+    let path = std::env::temp_dir().join("neith_test_binary_format.neithdb");
+    let _ = std::fs::remove_file(&path);
+    let con = Neith::connect_with_format(path.clone(), StorageFormat::Binary);
+    let _ = con.execute("new table parent with (id true, price false) check [price >= 0]").unwrap();
+    let _ = con.execute("new table child with (id true, parent false references parent(id) on delete cascade, tags false)").unwrap();
+    let _ = con.execute("create index on parent(price) ordered").unwrap();
+    let _ = con.execute("new data parent (id = 1,+ price = 0.30000000000000004)").unwrap();
+    let _ = con.execute("new data child (id = 1,+ parent = 1,+ tags = (a,+ true,+ 2.5))").unwrap();
+    let _ = con.execute("new data child (id = 2,+ parent = 1,+ tags = text)").unwrap();
+    let _ = con.save().unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(binard::is_binary(&bytes));
+    let mut reopened = Neith::connect(path.clone());
+    assert_eq!(reopened.get_storage_format(), StorageFormat::Binary);
+    for query in ["select * from parent", "select * from child"] {
+        assert_eq!(con.execute(query).unwrap(), reopened.execute(query).unwrap());
+    }
+    assert_eq!(reopened.execute("select (price) from parent").unwrap().get_result().unwrap()[0].get_list().unwrap()[0], Data::Float(0.1 + 0.2));
+    assert!(reopened.execute("new data parent (id = 2,+ price = -1)").is_err());
+    assert!(reopened.execute("new data child (id = 3,+ parent = 7,+ tags = none)").is_err());
+    // A damaged table is found by its checksum
+    let mut damaged = bytes.clone();
+    let last = damaged.len() - 1;
+    damaged[last] ^= 0xFF;
+    assert!(binard::decode_tables(&damaged).is_err());
+    assert!(binard::decode_tables(&bytes[..bytes.len() - 3]).is_err());
+    // Converting back to json
+    let _ = reopened.set_storage_format(StorageFormat::Json).unwrap();
+    assert!(!binard::is_binary(&std::fs::read(&path).unwrap()));
+    let json = Neith::connect(path.clone());
+    assert_eq!(json.get_storage_format(), StorageFormat::Json);
+    assert_eq!(con.execute("select * from child").unwrap(), json.execute("select * from child").unwrap());
+    // The format is shared, a clone saves in the format another handle switched to
+    let mut switched = Neith::connect(path.clone());
+    let clone = switched.clone();
+    let _ = switched.set_storage_format(StorageFormat::Binary).unwrap();
+    let _ = clone.execute("new data parent (id = 2,+ price = 2)").unwrap();
+    let _ = clone.save().unwrap();
+    assert_eq!(clone.get_storage_format(), StorageFormat::Binary);
+    assert!(binard::is_binary(&std::fs::read(&path).unwrap()));
+    let reopened = Neith::connect(path.clone());
+    assert_eq!(reopened.execute("get len of parent").unwrap().get_result().unwrap()[0].get_float().unwrap(), 2.0);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_json_versions() {
+    use crate::utils::jisard::{json_version, upgrade_json, JSON_VERSION};
+    let path = std::env::temp_dir().join("neith_test_json_versions.neithdb");
+    // A version 1 file, written before the header existed
+    let legacy = r#"{"neith":{"version":{"unique":false,"entry":["old"]}},"people":{"id":{"unique":true,"entry":[1,2]},"name":{"unique":false,"entry":["Ann","Bob"]}}}"#;
+    std::fs::write(&path, legacy).unwrap();
+    assert_eq!(json_version(&json::parse(legacy).unwrap()), 1);
+    let con = Neith::connect(path.clone());
+    let names = con.execute("select (name) from people where [id = 2]").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(names[0].get_string().unwrap(), "Bob");
+    assert!(con.execute("select (version) from neith").is_ok());
+    let _ = con.save().unwrap();
+    let saved = json::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json_version(&saved), JSON_VERSION);
+    assert!(saved["neith"]["metadata"]["saved"].is_string());
+    let reopened = Neith::connect(path.clone());
+    assert_eq!(reopened.execute("select * from people").unwrap(), con.execute("select * from people").unwrap());
+    let newer = json::parse(r#"{"neith":{"version":99,"metadata":{}},"tables":{}}"#).unwrap();
+    assert!(upgrade_json(newer).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_integrity_check() {
+    let path = std::env::temp_dir().join("neith_test_integrity_check.neithdb");
+    let _ = std::fs::remove_file(&path);
+    let con = Neith::connect(path.clone());
+    let _ = con.execute("new table people with (id true, name false, weight false)").unwrap();
+    let _ = con.execute("new table pets with (owner false references people(id), name false)").unwrap();
+    let _ = con.execute("new data people (id = 1,+ name = Ann,+ weight = 61.25)").unwrap();
+    let _ = con.execute("new data people (id = 2,+ name = Bob,+ weight = 0.1)").unwrap();
+    let _ = con.execute("new data pets (owner = 2,+ name = Rex)").unwrap();
+    let _ = con.save().unwrap();
+    let report = con.execute("integrity check").unwrap();
+    assert_eq!(report.get_column_names().unwrap(), vec!["check", "table", "passed", "details"]);
+    let columns = report.get_result().unwrap();
+    let checks = columns[0].get_list().unwrap();
+    let passed = columns[2].get_list().unwrap();
+    assert!(checks.iter().any(|check| check.get_string().unwrap() == "checksum"));
+    assert!(checks.iter().any(|check| check.get_string().unwrap() == "foreign keys"));
+    assert!(passed.iter().all(|entry| entry.get_bool().unwrap()));
+    // Changing the file by hand breaks the checksum of that table only
+    let contents = std::fs::read_to_string(&path).unwrap().replace("\"Bob\"", "\"Rob\"");
+    std::fs::write(&path, contents).unwrap();
+    let columns = con.verify().unwrap().get_result().unwrap();
+    let failed: Vec<(String, String)> = (0..columns[0].get_list().unwrap().len())
+        .filter(|row| !columns[2].get_list().unwrap()[*row].get_bool().unwrap())
+        .map(|row| (columns[0].get_list().unwrap()[row].get_string().unwrap(), columns[1].get_list().unwrap()[row].get_string().unwrap()))
+        .collect();
+    assert_eq!(failed, vec![("checksum".to_string(), "people".to_string())]);
+    std::fs::write(&path, "{\"neith\":").unwrap();
+    let columns = con.verify().unwrap().get_result().unwrap();
+    assert!(!columns[2].get_list().unwrap()[0].get_bool().unwrap());
+    assert!(con.execute("integrity checks").is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_encryption() {
+    let path = std::env::temp_dir().join("neith_test_encryption.neithdb");
+    let _ = std::fs::remove_file(&path);
+    let key = [42; 32];
+    let con = Neith::connect_encrypted(path.clone(), key).unwrap();
+    let _ = con.execute("new table secrets with (id true, secret false)").unwrap();
+    let _ = con.execute("new data secrets (id = 1,+ secret = swordfish)").unwrap();
+    let _ = con.save().unwrap();
+    let contents = std::fs::read(&path).unwrap();
+    assert!(contents.starts_with(b"NEITHENC"));
+    assert!(!String::from_utf8_lossy(&contents).contains("swordfish"));
+    let reopened = Neith::connect_encrypted(path.clone(), key).unwrap();
+    assert_eq!(reopened.execute("select * from secrets").unwrap(), con.execute("select * from secrets").unwrap());
+    assert!(reopened.verify().unwrap().get_result().unwrap()[2].get_list().unwrap().iter().all(|passed| passed.get_bool().unwrap()));
+    let wrong_key = Neith::connect_encrypted(path.clone(), [7; 32]);
+    assert!(wrong_key.unwrap_err().to_string().contains("Wrong key"));
+    // The binary format is encrypted just the same
+    let mut binary = Neith::connect_encrypted(path.clone(), key).unwrap();
+    let _ = binary.set_storage_format(crate::StorageFormat::Binary).unwrap();
+    let reopened = Neith::connect_encrypted(path.clone(), key).unwrap();
+    assert_eq!(reopened.get_storage_format(), crate::StorageFormat::Binary);
+    assert_eq!(reopened.execute("select * from secrets").unwrap(), con.execute("select * from secrets").unwrap());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compression() {
+    let path = std::env::temp_dir().join("neith_test_compression.neithdb");
+    let _ = std::fs::remove_file(&path);
+    // An uncompressed file still opens as usual
+    let plain = r#"{"neith":{"version":2,"metadata":{}},"tables":{"logs":{"id":{"unique":true,"entry":[1]},"message":{"unique":false,"entry":["started"]}}}}"#;
+    std::fs::write(&path, plain).unwrap();
+    let mut con = Neith::connect(path.clone());
+    assert!(!con.get_compression());
+    let _ = con.set_compression(true).unwrap();
+    for id in 2..200 {
+        let _ = con.execute(format!("new data logs (id = {id},+ message = the same message over and over again)").as_str()).unwrap();
+    }
+    let _ = con.save().unwrap();
+    let contents = std::fs::read(&path).unwrap();
+    assert!(contents.starts_with(&[0x1f, 0x8b]));
+    assert!(contents.len() < 200 * "the same message over and over again".len() / 4);
+    let reopened = Neith::connect(path.clone());
+    assert!(reopened.get_compression());
+    assert_eq!(reopened.execute("select * from logs").unwrap(), con.execute("select * from logs").unwrap());
+    assert!(reopened.verify().unwrap().get_result().unwrap()[2].get_list().unwrap().iter().all(|passed| passed.get_bool().unwrap()));
+    let mut binary = Neith::connect(path.clone());
+    let _ = binary.set_storage_format(crate::StorageFormat::Binary).unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]));
+    let reopened = Neith::connect(path.clone());
+    assert_eq!(reopened.get_storage_format(), crate::StorageFormat::Binary);
+    assert_eq!(reopened.execute("select * from logs").unwrap(), con.execute("select * from logs").unwrap());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_join() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table authors with (id true, name false)").unwrap();
+    let _ = con.execute("new table books with (title true, author_id false, year false)").unwrap();
+    let _ = con.execute("new data authors (id = 1,+ name = Ann)").unwrap();
+    let _ = con.execute("new data authors (id = 2,+ name = Bob)").unwrap();
+    let _ = con.execute("new data authors (id = 3,+ name = Cid)").unwrap();
+    let _ = con.execute("new data books (title = First,+ author_id = 1,+ year = 1990)").unwrap();
+    let _ = con.execute("new data books (title = Second,+ author_id = 2,+ year = 2001)").unwrap();
+    let _ = con.execute("new data books (title = Third,+ author_id = 1,+ year = 2010)").unwrap();
+    let inner = con.execute("select (authors.name, books.title) from authors join books on authors.id = books.author_id").unwrap();
+    assert_eq!(inner.get_column_names().unwrap(), vec!["authors.name", "books.title"]);
+    let columns = inner.get_result().unwrap();
+    let names: Vec<String> = columns[0].get_list().unwrap().iter().map(|name| name.get_string().unwrap()).collect();
+    let titles: Vec<String> = columns[1].get_list().unwrap().iter().map(|title| title.get_string().unwrap()).collect();
+    assert_eq!(names, vec!["Ann", "Ann", "Bob"]);
+    assert_eq!(titles, vec!["First", "Third", "Second"]);
+    // A left join keeps authors without books, the same with an index on the joined column
+    let left = con.execute("select (name, title) from authors left join books on books.author_id = authors.id where [authors.id >= 2] order by name desc").unwrap();
+    let _ = con.execute("create index on books(author_id)").unwrap();
+    assert_eq!(con.execute("select (name, title) from authors left join books on books.author_id = authors.id where [authors.id >= 2] order by name desc").unwrap(), left);
+    let columns = left.get_result().unwrap();
+    assert_eq!(columns[0].get_list().unwrap(), vec![Data::String("Cid".to_string()), Data::String("Bob".to_string())]);
+    assert_eq!(columns[1].get_list().unwrap(), vec![Data::Null(), Data::String("Second".to_string())]);
+    let filtered = con.execute("select (books.title) from authors inner join books on authors.id = books.author_id where [name = Ann,+ and year > 2000]").unwrap();
+    assert_eq!(filtered.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Third".to_string())]);
+    let all = con.execute("select * from books join authors on books.author_id = authors.id").unwrap();
+    assert_eq!(all.get_column_names().unwrap(), vec!["books.title", "books.author_id", "books.year", "authors.id", "authors.name"]);
+    assert!(con.execute("select (id) from authors join books on authors.id = books.author_id").is_ok());
+    assert!(con.execute("select (title) from authors join books on authors.id = books.nothing").is_err());
+    assert!(con.execute("select (name) from authors join authors on authors.id = authors.id").is_err());
+}
+
+#[test]
+fn test_subqueries() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table customers with (id true, name false)").unwrap();
+    let _ = con.execute("new table orders with (id true, customer false, total false)").unwrap();
+    for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cid")] {
+        let _ = con.execute(format!("new data customers (id = {id},+ name = {name})").as_str()).unwrap();
+    }
+    let _ = con.execute("new data orders (id = 10,+ customer = 1,+ total = 5)").unwrap();
+    let _ = con.execute("new data orders (id = 11,+ customer = 3,+ total = 50)").unwrap();
+    let _ = con.execute("new data orders (id = 12,+ customer = 1,+ total = 70)").unwrap();
+    let big_spenders = con.execute("select (name) from customers where [id in (select (customer) from orders where [total > 20,+ and total < 100])]").unwrap();
+    assert_eq!(big_spenders.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Ann".to_string()), Data::String("Cid".to_string())]);
+    // An empty result is an empty set, not an error
+    let nobody = con.execute("select (name) from customers where [id in (select (customer) from orders where [total > 100])]").unwrap();
+    assert!(nobody.get_result().unwrap()[0].get_list().unwrap().is_empty());
+    let ann = con.execute("select (name) from customers where [id in (select (customer) from orders where [total > 100]),+ or id = 1]").unwrap();
+    assert_eq!(ann.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Ann".to_string())]);
+    let everybody = con.execute("select (name) from customers where [id > 0,+ not id in (select (customer) from orders where [total > 100])]").unwrap();
+    assert_eq!(everybody.get_result().unwrap()[0].get_list().unwrap().len(), 3);
+    let exists = con.execute("select (name) from customers where [id > 1,+ and exists (select * from orders where [total = 50])]").unwrap();
+    assert_eq!(exists.get_result().unwrap()[0].get_list().unwrap().len(), 2);
+    let not_exists = con.execute("select (name) from customers where [id > 0,+ not exists (select * from orders where [total = 51])]").unwrap();
+    assert_eq!(not_exists.get_result().unwrap()[0].get_list().unwrap().len(), 3);
+    // Subqueries work wherever a condition list does
+    let _ = con.execute("update customers where [id in (select (customer) from orders where [total = 5])] with (name = Anna)").unwrap();
+    let _ = con.execute("delete data in orders where [customer in (select (id) from customers where [name = Cid])]").unwrap();
+    let left = con.execute("select (customers.name, orders.total) from customers join orders on customers.id = orders.customer where [customers.id in (select (id) from customers where [name = Anna])]").unwrap();
+    assert_eq!(left.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Anna".to_string()), Data::String("Anna".to_string())]);
+    assert_eq!(con.execute("get len of orders").unwrap().get_result().unwrap()[0].get_float().unwrap(), 2.0);
+    let plan = con.execute("explain select (name) from customers where [exists (select * from orders)]").unwrap();
+    assert_eq!(plan.get_result().unwrap()[2].get_list().unwrap()[0].get_string().unwrap(), "subquery");
+    assert!(con.execute("select (name) from customers where [id in (select * from orders)]").is_err());
+    assert!(con.execute("select (name) from customers where [id = (select (id) from orders)]").is_err());
+    // Only whole words outside of quotes make a subquery
+    assert!(con.execute("select (name) from customers where [id > 0,+ and notexists (select * from orders)]").is_err());
+    let _ = con.execute("new data customers (id = 4,+ name = 'Dee (select me)')").unwrap();
+    let quoted = con.execute("select (id) from customers where [name = 'Dee (select me)']").unwrap();
+    assert_eq!(quoted.get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(4.0)]);
+    // Values can't pose as the result of a subquery
+    assert!(con.execute("select (id) from customers where [name = \u{1}subquery0]").is_err());
+}
+
+#[test]
+fn test_expressions() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table counters with (id true, name false, count false, tags false)").unwrap();
+    let _ = con.execute("new data counters (id = 1,+ name = Visits,+ count = 10,+ tags = (a, b))").unwrap();
+    let _ = con.execute("new data counters (id = 2,+ name = clicks,+ count = 4)").unwrap();
+    let _ = con.execute("update counters where [id > 0] with (count = count + 1,+ name = upper(name))").unwrap();
+    let counters = con.execute("select (name, count) from counters").unwrap().get_result().unwrap();
+    assert_eq!(counters[0].get_list().unwrap(), vec![Data::String("VISITS".to_string()), Data::String("CLICKS".to_string())]);
+    assert_eq!(counters[1].get_list().unwrap(), vec![Data::Float(11.0), Data::Float(5.0)]);
+    let computed = con.execute("select (id, (count - 1) * 2, concat(lower(name), ': ', count), substr(name, 2, 3), length(name), len(tags), coalesce(tags, none)) from counters order by count").unwrap();
+    assert_eq!(computed.get_column_names().unwrap(), vec!["id", "(count - 1) * 2", "concat(lower(name), ': ', count)", "substr(name, 2, 3)", "length(name)", "len(tags)", "coalesce(tags, none)"]);
+    let columns = computed.get_result().unwrap();
+    assert_eq!(columns[0].get_list().unwrap(), vec![Data::Float(2.0), Data::Float(1.0)]);
+    assert_eq!(columns[1].get_list().unwrap(), vec![Data::Float(8.0), Data::Float(20.0)]);
+    assert_eq!(columns[2].get_list().unwrap(), vec![Data::String("clicks: 5".to_string()), Data::String("visits: 11".to_string())]);
+    assert_eq!(columns[3].get_list().unwrap(), vec![Data::String("LIC".to_string()), Data::String("ISI".to_string())]);
+    assert_eq!(columns[4].get_list().unwrap(), vec![Data::Float(6.0), Data::Float(6.0)]);
+    assert_eq!(columns[5].get_list().unwrap(), vec![Data::Null(), Data::Float(1.0)]);
+    assert_eq!(columns[6].get_list().unwrap()[0], Data::String("none".to_string()));
+    // Every expression reads the row as it was before the update.
+    let _ = con.execute("update counters where [id = 1] with (count = count * 2,+ id = count + 100,+ tags = append(tags, c))").unwrap();
+    let updated = con.execute("select * from counters where [name = VISITS]").unwrap().get_result().unwrap();
+    assert_eq!(updated[0].get_list().unwrap(), vec![Data::Float(111.0)]);
+    assert_eq!(updated[2].get_list().unwrap(), vec![Data::Float(22.0)]);
+    assert_eq!(updated[3].get_list().unwrap()[0].get_list().unwrap().len(), 2);
+    // Text that isn't computed is still written as it is.
+    let _ = con.execute("update counters where [id = 2] with (name = more text)").unwrap();
+    assert!(con.execute("select (name) from counters where [name = more text]").is_ok());
+    // Arithmetic without a column of the table isn't computed either.
+    let _ = con.execute("update counters where [id = 2] with (name = 555 - 1234,+ tags = a - b)").unwrap();
+    let texts = con.execute("select (name, tags) from counters where [id = 2]").unwrap().get_result().unwrap();
+    assert_eq!(texts[0].get_list().unwrap(), vec![Data::String("555 - 1234".to_string())]);
+    assert_eq!(texts[1].get_list().unwrap(), vec![Data::String("a - b".to_string())]);
+    assert!(con.execute("update counters where [id = 2] with (count = name + 1)").is_err());
+    // Quoted text is always written as it is, even if it names columns.
+    let _ = con.execute("update counters where [id = 2] with (name = 'count',+ tags = 'count - 1',+ count = 'count' * 2)").unwrap();
+    let quoted = con.execute("select (name, tags) from counters where [id = 2]").unwrap().get_result().unwrap();
+    assert_eq!(quoted[0].get_list().unwrap(), vec![Data::String("'count'".to_string())]);
+    assert_eq!(quoted[1].get_list().unwrap(), vec![Data::String("'count - 1'".to_string())]);
+    assert!(con.execute("select (id) from counters where [count = 'count' * 2]").is_ok());
+    assert!(con.execute("select (count / 0) from counters").is_err());
+    assert!(con.execute("select (upper(count)) from counters").is_err());
+}
+
+#[test]
+fn test_distinct_and_aliases() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table visits with (id true, city false, country false)").unwrap();
+    for (id, city, country) in [(1, "Paris", "France"), (2, "Lyon", "France"), (3, "Paris", "France"), (4, "Rome", "Italy")] {
+        let _ = con.execute(format!("new data visits (id = {id},+ city = {city},+ country = {country})").as_str()).unwrap();
+    }
+    let countries = con.execute("select distinct (country) from visits").unwrap();
+    assert_eq!(countries.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("France".to_string()), Data::String("Italy".to_string())]);
+    let cities = con.execute("select distinct (city, country) from visits where [id > 0] order by city desc").unwrap();
+    assert_eq!(cities.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Rome".to_string()), Data::String("Paris".to_string()), Data::String("Lyon".to_string())]);
+    let named = con.execute("select (country as land, city, upper(city) as shout) from visits where [id = 4]").unwrap();
+    assert_eq!(named.get_column_names().unwrap(), vec!["land", "city", "shout"]);
+    assert_eq!(named.get_result().unwrap()[2].get_list().unwrap(), vec![Data::String("ROME".to_string())]);
+    let _ = con.execute("new table countries with (name true, capital false)").unwrap();
+    let _ = con.execute("new data countries (name = France,+ capital = Paris)").unwrap();
+    let joined = con.execute("select distinct (countries.capital as capital) from visits join countries on visits.country = countries.name").unwrap();
+    assert_eq!(joined.get_column_names().unwrap(), vec!["capital"]);
+    assert_eq!(joined.get_result().unwrap()[0].get_list().unwrap(), vec![Data::String("Paris".to_string())]);
+    let plan = con.execute("explain select distinct (city) from visits").unwrap();
+    assert_eq!(plan.get_result().unwrap()[1].get_list().unwrap().last().unwrap().get_string().unwrap(), "distinct");
+}
+
+#[test]
+fn test_upsert() {
+    use crate::data::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table stock with (sku true, name true, amount false) check [amount >= 0]").unwrap();
+    let _ = con.execute("new data stock (sku = a1,+ name = bolt,+ amount = 10) on conflict (sku) update").unwrap();
+    let _ = con.execute("new data stock (sku = b2,+ name = nut,+ amount = 5)").unwrap();
+    assert!(con.execute("new data stock (sku = a1,+ name = screw,+ amount = 1)").is_err());
+    let _ = con.execute("new data stock (sku = a1,+ name = screw,+ amount = 7) on conflict (sku) update").unwrap();
+    let _ = con.execute("new data stock (sku = b2,+ name = washer,+ amount = 99) on conflict (sku) ignore").unwrap();
+    let stock = con.execute("select * from stock").unwrap().get_result().unwrap();
+    assert_eq!(stock[1].get_list().unwrap(), vec![Data::String("screw".to_string()), Data::String("nut".to_string())]);
+    assert_eq!(stock[2].get_list().unwrap(), vec![Data::Float(7.0), Data::Float(5.0)]);
+    // A violated constraint during the update leaves the row as it was.
+    assert!(con.execute("new data stock (sku = a1,+ name = nut,+ amount = 1) on conflict (sku) update").is_err());
+    assert!(con.execute("new data stock (sku = a1,+ name = bolt,+ amount = -1) on conflict (sku) update").is_err());
+    let unchanged = con.execute("select (name, amount) from stock where [sku = a1]").unwrap().get_result().unwrap();
+    assert_eq!(unchanged[0].get_list().unwrap(), vec![Data::String("screw".to_string())]);
+    assert_eq!(unchanged[1].get_list().unwrap(), vec![Data::Float(7.0)]);
+    assert!(con.execute("new data stock (sku = c3,+ amount = 1) on conflict (amount) update").is_err());
+    assert!(con.execute("new data stock (sku = c3,+ amount = 1) on conflict (sku) replace").is_err());
+    assert_eq!(con.execute("get len of stock").unwrap().get_result().unwrap()[0].get_float().unwrap(), 2.0);
+    // Text may hold parenthesis of its own
+    let _ = con.execute("new data stock (sku = d4,+ name = see item 3) for details,+ amount = 1)").unwrap();
+    let _ = con.execute("new data stock (sku = e5,+ name = (unsorted,+ amount = 2) on conflict (sku) ignore").unwrap();
+    let names = con.execute("select (name) from stock where [amount < 3]").unwrap().get_result().unwrap();
+    assert_eq!(names[0].get_list().unwrap(), vec![Data::String("see item 3) for details".to_string()), Data::String("(unsorted".to_string())]);
+    // Rows are checked together, so two rows can swap the entries of a unique column.
+    let _ = con.execute("new data stock (sku = a1,+ name = nut), (sku = b2,+ name = screw) on conflict (sku) update").unwrap();
+    let swapped = con.execute("select (name) from stock where [amount > 3]").unwrap().get_result().unwrap();
+    assert_eq!(swapped[0].get_list().unwrap(), vec![Data::String("nut".to_string()), Data::String("screw".to_string())]);
+}
+
+#[test]
+fn test_insert_many() {
+    use crate::Data;
+    let con = Neith::connect_ram_mode(true);
+    let _ = con.execute("new table points with (id true, x false, y false) check [x < 100]").unwrap();
+    let _ = con.execute("new data points (id = 1,+ x = 0,+ y = 0), (id = 2,+ x = 1,+ y = 2),(id = 3,+ x = -1)").unwrap();
+    assert_eq!(con.execute("get len of points").unwrap().get_result().unwrap()[0].get_float().unwrap(), 3.0);
+    assert_eq!(con.execute("select (y) from points where [id = 3]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Null()]);
+    // A single row violating a constraint keeps every row out.
+    assert!(con.execute("new data points (id = 4,+ x = 5), (id = 4,+ x = 6)").is_err());
+    assert!(con.execute("new data points (id = 5,+ x = 5), (id = 1,+ x = 6)").is_err());
+    assert_eq!(con.execute("get len of points").unwrap().get_result().unwrap()[0].get_float().unwrap(), 3.0);
+    let _ = con.execute("new data points (id = 1,+ x = 10), (id = 6,+ x = 6) on conflict (id) update").unwrap();
+    assert_eq!(con.execute("select (x) from points where [id = 1]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(10.0)]);
+    let _ = con.execute("new data points (id = 2,+ x = 20), (id = 7,+ x = 7), (id = 7,+ x = 8) on conflict (id) ignore").unwrap();
+    assert_eq!(con.execute("select (x) from points where [id = 7]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(7.0)]);
+    assert!(con.execute("new data points (id = 2,+ x = 20), (id = 8,+ x = 8), (id = 3,+ x = 500) on conflict (id) update").is_err());
+    assert_eq!(con.execute("select (x) from points where [id = 2]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(1.0)]);
+    assert!(con.execute("select (x) from points where [id = 8]").is_err());
+    assert_eq!(con.execute("select (id) from points where [x = 1]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(2.0)]);
+    let rows: Vec<Vec<(String, Data)>> = (10..110).map(|id| vec![("id".to_string(), Data::Float(id as f64)), ("x".to_string(), Data::Float(1.0))]).collect();
+    let _ = con.insert_many("points", rows).unwrap();
+    assert_eq!(con.execute("get len of points").unwrap().get_result().unwrap()[0].get_float().unwrap(), 105.0);
+    // A single entry in the job history for all of the rows.
+    let history = con.execute("select (command) from job_history").unwrap().get_result().unwrap()[0].get_list().unwrap();
+    assert_eq!(history.iter().filter(|command| command.get_string().unwrap().contains("insert many")).count(), 1);
+    assert!(con.insert_many("points", vec![vec![("id".to_string(), Data::Float(200.0))], vec![("id".to_string(), Data::Float(10.0))]]).is_err());
+    assert!(con.insert_many("nowhere", Vec::new()).is_err());
+    assert_eq!(con.execute("get len of points").unwrap().get_result().unwrap()[0].get_float().unwrap(), 105.0);
+    // Rows of the same statement can point to each other.
+    let _ = con.execute("new table staff with (id true, boss false references staff(id) on delete restrict)").unwrap();
+    let _ = con.execute("new data staff (id = 1), (id = 2,+ boss = 1), (id = 3,+ boss = 4), (id = 4,+ boss = 2)").unwrap();
+    assert!(con.execute("new data staff (id = 5,+ boss = 9), (id = 6,+ boss = 5)").is_err());
+    assert_eq!(con.execute("get len of staff").unwrap().get_result().unwrap()[0].get_float().unwrap(), 4.0);
+}
+
+#[test]
+fn test_alter() {
+    use crate::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table owners with (id true, name false)").unwrap();
+    let _ = con.execute("new table pets with (id true, owner false references owners(id) on delete cascade, age false) check [age >= 0]").unwrap();
+    let _ = con.execute("new data owners (id = 1,+ name = ann), (id = 2,+ name = bob)").unwrap();
+    let _ = con.execute("new data pets (id = 1,+ owner = 1,+ age = 3), (id = 2,+ owner = 2,+ age = 3)").unwrap();
+    let _ = con.execute("alter table owners rename to people").unwrap();
+    assert!(con.execute("select * from owners").is_err());
+    let _ = con.execute("alter column id in people rename to person_id").unwrap();
+    let _ = con.execute("alter column age in pets rename to years").unwrap();
+    // The foreign key and check constraint follow the new names.
+    assert!(con.execute("new data pets (id = 3,+ owner = 7,+ years = 1)").is_err());
+    assert!(con.execute("new data pets (id = 3,+ owner = 1,+ years = -1)").is_err());
+    let _ = con.execute("delete data in people where [person_id = 2]").unwrap();
+    assert_eq!(con.execute("get len of pets").unwrap().get_result().unwrap()[0].get_float().unwrap(), 1.0);
+    assert!(con.execute("alter table pets rename to people").is_err());
+    assert!(con.execute("alter column years in pets rename to owner").is_err());
+    // Existing entries are checked before a column is marked unique.
+    let _ = con.execute("new data pets (id = 3,+ owner = 1,+ years = 3)").unwrap();
+    assert!(con.execute("alter column years in pets set unique true").is_err());
+    let _ = con.execute("alter column owner in pets set unique false").unwrap();
+    let _ = con.execute("alter column id in pets set unique false").unwrap();
+    let _ = con.execute("new data pets (id = 3,+ owner = 1,+ years = 4)").unwrap();
+    assert!(con.execute("alter column id in pets set unique true").is_err());
+    let _ = con.execute("delete data in pets where [id = 1]").unwrap();
+    let _ = con.execute("alter column years in pets set unique true").unwrap();
+    assert!(con.execute("new data pets (id = 9,+ owner = 1,+ years = 4)").is_err());
+    assert_eq!(con.execute("select (id) from pets").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(3.0), Data::Float(3.0)]);
+    assert!(con.execute("alter column years in pets set unique maybe").is_err());
+    assert!(con.execute("alter index years in pets").is_err());
+}
+
+#[test]
+fn test_schema() {
+    use crate::Data;
+    let con = Neith::connect_ram_mode(true);
+    let _ = con.execute("new table authors with (id true, name false)").unwrap();
+    let _ = con.execute("new table books with (id true, author false references authors(id) on delete cascade, pages false) check positive [pages > 0]").unwrap();
+    let _ = con.execute("create index on books(pages) ordered").unwrap();
+    let _ = con.execute("new data authors (id = 1,+ name = ann), (id = 2,+ name = bob)").unwrap();
+    let schema = con.schema().unwrap();
+    assert_eq!(schema.iter().map(|table| table.name.as_str()).collect::<Vec<&str>>(), vec!["job_history", "authors", "books"]);
+    assert_eq!(schema[1].row_count, 2);
+    assert_eq!(schema[2].checks, vec!["positive".to_string()]);
+    assert!(schema[2].columns[0].unique);
+    assert_eq!(schema[2].columns[1].references, Some("authors(id) on delete cascade".to_string()));
+    assert_eq!(schema[2].columns[2].index, Some("ordered".to_string()));
+    let tables = con.execute("show tables").unwrap();
+    assert_eq!(tables.get_column_names().unwrap(), vec!["table", "rows", "columns", "checks"]);
+    assert_eq!(tables.get_result().unwrap()[2].get_list().unwrap(), vec![Data::Float(4.0), Data::Float(2.0), Data::Float(3.0)]);
+    let described = con.execute("describe books").unwrap().get_result().unwrap();
+    assert_eq!(described[0].get_list().unwrap(), vec![Data::String("id".to_string()), Data::String("author".to_string()), Data::String("pages".to_string())]);
+    assert_eq!(described[1].get_list().unwrap(), vec![Data::Bool(true), Data::Bool(false), Data::Bool(false)]);
+    assert_eq!(described[2].get_list().unwrap(), vec![Data::String("hash".to_string()), Data::Null(), Data::String("ordered".to_string())]);
+    assert_eq!(described[3].get_list().unwrap()[1], Data::String("authors(id) on delete cascade".to_string()));
+    assert!(con.execute("describe nothing").is_err());
+    assert!(con.execute("show columns").is_err());
+}
+
+#[test]
+fn test_copy_table() {
+    use crate::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table staff with (id true, boss false references staff(id), salary false) check [salary > 0]").unwrap();
+    let _ = con.execute("new data staff (id = 1,+ salary = 100)").unwrap();
+    let _ = con.execute("new data staff (id = 2,+ boss = 1,+ salary = 50), (id = 3,+ boss = 1,+ salary = 60)").unwrap();
+    let _ = con.execute("copy table staff to staff_backup").unwrap();
+    let _ = con.execute("update staff where [boss = 1] with (salary = salary * 2)").unwrap();
+    let backup = con.execute("select (salary) from staff_backup").unwrap().get_result().unwrap();
+    assert_eq!(backup[0].get_list().unwrap(), vec![Data::Float(100.0), Data::Float(50.0), Data::Float(60.0)]);
+    // Constraints are copied, the self-reference points to the copy.
+    assert!(con.execute("new data staff_backup (id = 1,+ salary = 10)").is_err());
+    assert!(con.execute("new data staff_backup (id = 4,+ salary = -10)").is_err());
+    let _ = con.execute("delete data in staff where [id = 3]").unwrap();
+    assert!(con.execute("new data staff_backup (id = 4,+ boss = 3,+ salary = 10)").is_ok());
+    assert!(con.execute("copy table staff to staff_backup").is_err());
+    assert!(con.execute("copy table nothing to other").is_err());
+    let _ = con.execute("new table well_paid as select (id, salary, salary / 10 as bonus) from staff where [salary >= 100] order by salary").unwrap();
+    let schema = con.schema().unwrap();
+    let well_paid = schema.iter().find(|table| table.name == "well_paid").unwrap();
+    assert_eq!(well_paid.row_count, 2);
+    assert!(well_paid.columns[0].unique);
+    assert!(!well_paid.columns[2].unique);
+    assert_eq!(con.execute("select (bonus) from well_paid").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(10.0), Data::Float(10.0)]);
+    assert!(con.execute("new data well_paid (id = 1,+ salary = 5)").is_err());
+    let _ = con.execute("new table nobody as select * from staff where [salary > 1000]").unwrap();
+    assert_eq!(con.execute("describe nobody").unwrap().get_result().unwrap()[0].get_list().unwrap().len(), 3);
+    assert_eq!(con.execute("get len of nobody").unwrap().get_result().unwrap()[0].get_float().unwrap(), 0.0);
+    let _ = con.execute("new table bosses as select distinct (boss) from staff where [boss = 1]").unwrap();
+    assert_eq!(con.execute("get len of bosses").unwrap().get_result().unwrap()[0].get_float().unwrap(), 1.0);
+    assert!(con.execute("new table broken as select (salary * 2) from staff").is_err());
+    assert!(con.execute("new table bosses as select * from staff").is_err());
+    assert!(con.execute("new table broken as delete table with staff").is_err());
+    // The self-reference points to the new table, so every row it points to has to be selected
+    let _ = con.execute("new table team as select (id, boss) from staff").unwrap();
+    let team = con.schema().unwrap().into_iter().find(|table| table.name == "team").unwrap();
+    assert!(team.columns[1].references.as_ref().unwrap().starts_with("team(id)"));
+    assert!(con.execute("new table juniors as select (id, boss) from staff where [id = 2]").is_err());
+    assert!(con.execute("new table joined as select (staff.id, staff_backup.salary) from staff join staff_backup on staff.id = staff_backup.id").is_err());
+}
+
+#[test]
+fn test_truncate() {
+    use crate::Data;
+    let con = Neith::connect_ram_mode(false);
+    let _ = con.execute("new table lists with (id true, name false) check [id > 0]").unwrap();
+    let _ = con.execute("new table items with (id true, list false references lists(id) on delete cascade)").unwrap();
+    let _ = con.execute("new table tags with (id true, list false references lists(id))").unwrap();
+    let _ = con.execute("create index on lists(name) ordered").unwrap();
+    let _ = con.execute("new data lists (id = 1,+ name = a), (id = 2,+ name = b)").unwrap();
+    let _ = con.execute("new data items (id = 1,+ list = 1), (id = 2,+ list = 2)").unwrap();
+    let _ = con.execute("new data tags (id = 1,+ list = 2)").unwrap();
+    // Restricting foreign keys are respected, nothing is deleted.
+    assert!(con.execute("truncate lists").is_err());
+    assert_eq!(con.execute("get len of items").unwrap().get_result().unwrap()[0].get_float().unwrap(), 2.0);
+    let _ = con.execute("truncate tags").unwrap();
+    let _ = con.execute("truncate lists").unwrap();
+    assert_eq!(con.execute("get len of lists").unwrap().get_result().unwrap()[0].get_float().unwrap(), 0.0);
+    assert_eq!(con.execute("get len of items").unwrap().get_result().unwrap()[0].get_float().unwrap(), 0.0);
+    let _ = con.execute("truncate lists").unwrap();
+    // An empty column has no minimum or maximum, with or without an index.
+    assert_eq!(con.execute("get min in name from lists").unwrap().get_result().unwrap(), vec![Data::Null()]);
+    assert_eq!(con.execute("get max in id from lists").unwrap().get_result().unwrap(), vec![Data::Null()]);
+    assert_eq!(con.execute("get max in list from items").unwrap().get_result().unwrap(), vec![Data::Null()]);
+    // Columns, indexes and constraints are kept.
+    let described = con.execute("describe lists").unwrap().get_result().unwrap();
+    assert_eq!(described[2].get_list().unwrap(), vec![Data::String("hash".to_string()), Data::String("ordered".to_string())]);
+    let _ = con.execute("new data lists (id = 1,+ name = c)").unwrap();
+    assert!(con.execute("new data lists (id = 1,+ name = d)").is_err());
+    assert!(con.execute("new data lists (id = -1,+ name = d)").is_err());
+    assert_eq!(con.execute("select (id) from lists where [name = c]").unwrap().get_result().unwrap()[0].get_list().unwrap(), vec![Data::Float(1.0)]);
+    assert!(con.execute("truncate nothing").is_err());
+    assert!(con.execute("truncate lists items").is_err());
+}
@@ -0,0 +1,316 @@
+//! This is the Binary-Wizard or Binard for short.
+//! It contains the logic for reading and writing the binary `.neithdb` format.
+//!
+//! The file starts with the magic bytes `NEITHDB\0`, followed by the format version (`u16`) and
+//! the number of tables (`u32`). Every table is a checksum (`u32`) and a length (`u64`), followed
+//! by that many bytes encoding the table. All numbers are little endian, every string and list
+//! is prefixed by its length.
+
+use std::io::{Error, ErrorKind};
+
+use crate::{database::{Database, Storage}, data::Data, success::Success, table::Table, column::Column, constraint::{Check, ForeignKey, OnDelete}, condition::{Condition, Operator}, utils::{util::checksum, jisard::write_neithdb_bytes}};
+
+/// Every binary `.neithdb` file starts with these bytes.
+pub const MAGIC_BYTES: &[u8; 8] = b"NEITHDB\0";
+/// The version of the binary format written by this version of Neith.
+pub const BINARY_VERSION: u16 = 1;
+
+/// Checks if the supplied file contents are in the binary format.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    return bytes.starts_with(MAGIC_BYTES);
+}
+
+/// Takes the database and writes it to file in the binary format, as the storage settings say.
+///
+/// ## Returns
+/// A generic Success message.
+///
+/// ## Errors
+/// If a table couldn't be locked, or during saving to disc.
+pub fn write_binary_neithdb_file(database: &Database, storage: &Storage) -> Result<Success, Error> {
+    let encoded_tables = database.encode_tables(encode_table)?;
+    write_neithdb_bytes(storage, &storage.path, encode_binary_neithdb(encoded_tables))?;
+    return Ok(Success::SuccessMessage(true));
+}
+
+/// Puts the magic bytes, version and table count in front of the supplied tables, encoded by
+/// `encode_table`.
+///
+/// ## Returns
+/// The contents of a binary file holding the tables, in the order they were supplied.
+pub fn encode_binary_neithdb(encoded_tables: Vec<(String, Vec<u8>)>) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    out.extend(MAGIC_BYTES);
+    out.extend(BINARY_VERSION.to_le_bytes());
+    out.extend((encoded_tables.len() as u32).to_le_bytes());
+    for (_, table) in encoded_tables {
+        out.extend(table);
+    }
+    return out;
+}
+
+/// Decodes every table out of the contents of a binary file.
+///
+/// ## Errors
+/// If the contents aren't in the binary format, or the checksum of a table doesn't match.
+pub fn decode_tables(bytes: &[u8]) -> Result<Vec<Table>, Error> {
+    if !is_binary(bytes) {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a binary neithdb file!"));
+    }
+    let mut reader = Reader { bytes, position: MAGIC_BYTES.len() };
+    let version = u16::from_le_bytes(reader.take_array()?);
+    if version > BINARY_VERSION {
+        return Err(Error::new(ErrorKind::InvalidData, format!("Binary format version {} is newer than the supported version {}!", version, BINARY_VERSION)));
+    }
+    let table_count = reader.read_u32()?;
+    let mut out: Vec<Table> = Vec::new();
+    for _ in 0..table_count {
+        let expected = reader.read_u32()?;
+        let length = reader.read_u64()? as usize;
+        let payload = reader.take(length)?;
+        if checksum(payload) != expected {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Checksum mismatch in table {} of the binary file!", out.len())));
+        }
+        out.push(decode_table(payload)?);
+    }
+    return Ok(out);
+}
+
+/// Recomputes the checksum of every table in the contents of a binary file, and compares it to
+/// the saved one.
+///
+/// ## Returns
+/// The name of every table, along with `Some(true)` if the checksums match.
+///
+/// ## Errors
+/// If the contents aren't in the binary format, or are cut short.
+pub fn verify_checksums(bytes: &[u8]) -> Result<Vec<(String, Option<bool>)>, Error> {
+    if !is_binary(bytes) {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a binary neithdb file!"));
+    }
+    let mut reader = Reader { bytes, position: MAGIC_BYTES.len() };
+    let _version = u16::from_le_bytes(reader.take_array()?);
+    let table_count = reader.read_u32()?;
+    let mut out: Vec<(String, Option<bool>)> = Vec::new();
+    for counter in 0..table_count {
+        let expected = reader.read_u32()?;
+        let length = reader.read_u64()? as usize;
+        let payload = reader.take(length)?;
+        // The name could be damaged as well.
+        let name = Reader { bytes: payload, position: 0 }.read_string().unwrap_or(format!("table {}", counter));
+        out.push((name, Some(checksum(payload) == expected)));
+    }
+    return Ok(out);
+}
+
+/// Encodes a single table, prefixed by its checksum and length.
+pub fn encode_table(table: &Table) -> Result<Vec<u8>, Error> {
+    let mut payload: Vec<u8> = Vec::new();
+    write_string(&mut payload, &table.name);
+    payload.extend((table.columns.len() as u32).to_le_bytes());
+    for column in &table.columns {
+        write_string(&mut payload, &column.name);
+        payload.push(column.unique as u8);
+        match &column.index {
+            None => payload.push(0),
+            Some(index) if index.is_ordered() => payload.push(2),
+            Some(_) => payload.push(1),
+        }
+        match &column.references {
+            None => payload.push(0),
+            Some(reference) => {
+                payload.push(1);
+                write_string(&mut payload, &reference.table);
+                write_string(&mut payload, &reference.column);
+                write_string(&mut payload, &reference.on_delete.to_nql());
+            },
+        }
+        payload.extend((column.contents.all_row_data.len() as u64).to_le_bytes());
+        for data in &column.contents.all_row_data {
+            write_data(&mut payload, data);
+        }
+    }
+    payload.extend((table.checks.len() as u32).to_le_bytes());
+    for check in &table.checks {
+        write_string(&mut payload, &check.name);
+        payload.extend((check.conditions.len() as u32).to_le_bytes());
+        for condition in &check.conditions {
+            match condition {
+                Condition::Compare(column, operator, data) => {
+                    payload.push(0);
+                    write_string(&mut payload, column);
+                    write_string(&mut payload, &operator.to_nql());
+                    write_data(&mut payload, data);
+                },
+                Condition::Connector(connector) => {
+                    payload.push(1);
+                    write_string(&mut payload, connector);
+                },
+                Condition::Exists(exists) => {
+                    payload.push(2);
+                    payload.push(*exists as u8);
+                },
+            }
+        }
+    }
+    let mut out: Vec<u8> = Vec::new();
+    out.extend(checksum(&payload).to_le_bytes());
+    out.extend((payload.len() as u64).to_le_bytes());
+    out.extend(payload);
+    return Ok(out);
+}
+
+/// Decodes a single table, without its checksum and length.
+fn decode_table(payload: &[u8]) -> Result<Table, Error> {
+    let mut reader = Reader { bytes: payload, position: 0 };
+    let name = reader.read_string()?;
+    let column_count = reader.read_u32()?;
+    let mut columns: Vec<Column> = Vec::new();
+    for _ in 0..column_count {
+        let columnname = reader.read_string()?;
+        let unique = reader.read_u8()? == 1;
+        let index_type = match reader.read_u8()? {
+            0 => None,
+            1 => Some("hash"),
+            2 => Some("ordered"),
+            other => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown index type {} in binary neithdb file!", other))),
+        };
+        let references = if reader.read_u8()? == 1 {
+            let table = reader.read_string()?;
+            let column = reader.read_string()?;
+            let on_delete = OnDelete::from_nql(&reader.read_string()?)?;
+            Some(ForeignKey { table, column, on_delete })
+        } else {
+            None
+        };
+        let row_count = reader.read_u64()?;
+        let mut all_row_data: Vec<Data> = Vec::new();
+        for _ in 0..row_count {
+            all_row_data.push(reader.read_data()?);
+        }
+        columns.push(Column::from_saved(columnname, unique, references, index_type, all_row_data));
+    }
+    let check_count = reader.read_u32()?;
+    let mut checks: Vec<Check> = Vec::new();
+    for _ in 0..check_count {
+        let checkname = reader.read_string()?;
+        let condition_count = reader.read_u32()?;
+        let mut conditions: Vec<Condition> = Vec::new();
+        for _ in 0..condition_count {
+            match reader.read_u8()? {
+                0 => {
+                    let column = reader.read_string()?;
+                    let operator = reader.read_string()?;
+                    let operator = Operator::from_nql(&operator);
+                    if operator.is_none() {
+                        return Err(Error::new(ErrorKind::InvalidData, "Invalid operator in check constraint!"));
+                    }
+                    conditions.push(Condition::Compare(column, operator.unwrap(), reader.read_data()?));
+                },
+                1 => conditions.push(Condition::Connector(reader.read_string()?)),
+                2 => conditions.push(Condition::Exists(reader.read_u8()? == 1)),
+                other => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown condition type {} in check constraint!", other))),
+            }
+        }
+        checks.push(Check { name: checkname, conditions });
+    }
+    let mut out = Table::from((name, columns));
+    out.checks = checks;
+    return Ok(out);
+}
+
+/// Writes the length of the string, followed by the string itself.
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    out.extend((value.len() as u32).to_le_bytes());
+    out.extend(value.as_bytes());
+}
+
+/// Writes the type of the data, followed by the data itself. Lists of any depth are supported.
+fn write_data(out: &mut Vec<u8>, data: &Data) {
+    match data {
+        Data::Null() => out.push(0),
+        Data::Bool(boolean) => {
+            out.push(1);
+            out.push(*boolean as u8);
+        },
+        Data::Float(float) => {
+            out.push(2);
+            out.extend(float.to_le_bytes());
+        },
+        Data::String(string) => {
+            out.push(3);
+            write_string(out, string);
+        },
+        Data::List(list) => {
+            out.push(4);
+            out.extend((list.len() as u32).to_le_bytes());
+            for entry in list {
+                write_data(out, entry);
+            }
+        },
+    }
+}
+
+/// Reads the binary format step by step, erroring instead of reading past the end.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() - self.position < length {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Binary neithdb file is truncated!"));
+        }
+        let out = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        return Ok(out);
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        return Ok(out);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        return Ok(u32::from_le_bytes(self.take_array()?));
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Error> {
+        return Ok(u64::from_le_bytes(self.take_array()?));
+    }
+
+    fn read_string(&mut self) -> Result<String, Error> {
+        let length = self.read_u32()? as usize;
+        let bytes = self.take(length)?;
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(out) => return Ok(out),
+            Err(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid string in binary neithdb file!")),
+        }
+    }
+
+    fn read_data(&mut self) -> Result<Data, Error> {
+        match self.read_u8()? {
+            0 => return Ok(Data::Null()),
+            1 => return Ok(Data::Bool(self.read_u8()? == 1)),
+            2 => return Ok(Data::Float(f64::from_le_bytes(self.take_array()?))),
+            3 => return Ok(Data::String(self.read_string()?)),
+            4 => {
+                let length = self.read_u32()?;
+                let mut list: Vec<Data> = Vec::new();
+                for _ in 0..length {
+                    list.push(self.read_data()?);
+                }
+                return Ok(Data::List(list));
+            },
+            other => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown data type {} in binary neithdb file!", other))),
+        }
+    }
+
+}
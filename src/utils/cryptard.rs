@@ -0,0 +1,66 @@
+//! This is the Crypto-Wizard or Cryptard for short.
+//! It contains the logic for encrypting and decrypting a `.neithdb` file.
+//!
+//! An encrypted file starts with the magic bytes `NEITHENC`, followed by a random nonce (12
+//! bytes) and the json or binary file, encrypted and authenticated with ChaCha20-Poly1305.
+
+use std::{io::{Error, ErrorKind}, fmt};
+
+use chacha20poly1305::{ChaCha20Poly1305, Nonce, aead::{Aead, AeadCore, KeyInit, OsRng}};
+
+/// Every encrypted `.neithdb` file starts with these bytes.
+pub const MAGIC_BYTES: &[u8; 8] = b"NEITHENC";
+/// The length of the nonce following the magic bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// The 32 byte key a database is encrypted with. It is never printed.
+#[derive(Clone, PartialEq)]
+pub struct Key(pub [u8; 32]);
+
+impl fmt::Debug for Key {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "Key(..)");
+    }
+
+}
+
+/// Checks if the supplied file contents are encrypted.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    return bytes.starts_with(MAGIC_BYTES);
+}
+
+/// Encrypts the contents of a file with the key, using a new random nonce.
+///
+/// ## Errors
+/// If the contents couldn't be encrypted.
+pub fn encrypt(key: &Key, plain: &[u8]) -> Result<Vec<u8>, Error> {
+    let cipher = ChaCha20Poly1305::new(&key.0.into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let encrypted = cipher.encrypt(&nonce, plain);
+    if encrypted.is_err() {
+        return Err(Error::other("Couldn't encrypt the neithdb file!"));
+    }
+    let mut out: Vec<u8> = Vec::new();
+    out.extend(MAGIC_BYTES);
+    out.extend(nonce);
+    out.extend(encrypted.unwrap());
+    return Ok(out);
+}
+
+/// Decrypts the contents of an encrypted file with the key.
+///
+/// ## Errors
+/// If the contents aren't encrypted, the key is wrong, or the file was changed since it was
+/// encrypted.
+pub fn decrypt(key: &Key, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    if !is_encrypted(bytes) || bytes.len() < MAGIC_BYTES.len() + NONCE_LENGTH {
+        return Err(Error::new(ErrorKind::InvalidData, "Not an encrypted neithdb file!"));
+    }
+    let (nonce, encrypted) = bytes[MAGIC_BYTES.len()..].split_at(NONCE_LENGTH);
+    let cipher = ChaCha20Poly1305::new(&key.0.into());
+    match cipher.decrypt(Nonce::from_slice(nonce), encrypted) {
+        Ok(out) => return Ok(out),
+        Err(_) => return Err(Error::new(ErrorKind::InvalidData, "Wrong key, or the encrypted neithdb file is damaged!")),
+    }
+}
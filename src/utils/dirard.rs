@@ -0,0 +1,232 @@
+//! This is the Directory-Wizard or Dirard for short.
+//! It contains the logic for reading and writing a database laid out as a file for each table.
+//!
+//! The `.neithdb` directory holds a manifest, listing the name and file of every table in the
+//! order they were created. Every table file is a `.neithdb` file of its own in either format,
+//! holding just that table. Compression and encryption apply to every file, the manifest included.
+
+use std::{fs, io::{Error, ErrorKind}, path::Path};
+
+use json::JsonValue;
+
+use crate::{database::{Database, SavedTable, SavedTables, Storage}, format::StorageFormat, success::Success, table::Table, utils::{binard, jisard::{self, read_neithdb_bytes, write_neithdb_bytes, decompress_neithdb_bytes, parse_neithdb_json, upgrade_json}}};
+
+/// The file listing every table, inside the directory of the database.
+pub const MANIFEST: &str = "manifest.neithdb";
+/// The version of the manifest written by this version of Neith.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Writes every table that changed since the last save to a new file, and the manifest listing
+/// the file of each table afterwards. Files the manifest no longer lists are removed last, so the
+/// directory always holds a complete database.
+///
+/// ## Returns
+/// A generic Success message.
+///
+/// ## Errors
+/// If a table couldn't be locked or encoded, or during saving to disc.
+pub fn write_table_files(database: &Database, storage: &Storage) -> Result<Success, Error> {
+    fs::create_dir_all(&storage.path)?;
+    // The tables are read before the saved tables are locked, adding a table locks them the other
+    // way around.
+    let tables = database.get_tables()?;
+    let saved_store = database.saved_tables.lock();
+    if saved_store.is_err() {
+        return Err(Error::other("Couldn't lock saved tables!"));
+    }
+    let mut saved_tables = saved_store.unwrap();
+    let mut next_file = next_file_number(&storage.path)?;
+    let mut manifest: Vec<(String, String)> = Vec::new();
+    for entry in tables {
+        let table = entry.read();
+        if table.is_err() {
+            return Err(Error::other("Couldn't lock table!"));
+        }
+        let ok_table = table.unwrap();
+        let saved = match saved_tables.get(&ok_table.name) {
+            Some((changes, SavedTable::File(filename))) if *changes == ok_table.changes => Some(filename.clone()),
+            _ => None,
+        };
+        let filename = if saved.is_some() {
+            saved.unwrap()
+        } else {
+            // A changed table gets a new file, the old one is still listed until the manifest is written.
+            let filename = format!("table{}.neithdb", next_file);
+            next_file += 1;
+            let contents = match storage.format {
+                StorageFormat::Json => jisard::encode_neithdb_json(vec![(ok_table.name.clone(), jisard::encode_table_bytes(&ok_table)?)]),
+                StorageFormat::Binary => binard::encode_binary_neithdb(vec![(ok_table.name.clone(), binard::encode_table(&ok_table)?)]),
+            };
+            write_neithdb_bytes(storage, &storage.path.join(&filename), contents)?;
+            let _ = saved_tables.insert(ok_table.name.clone(), (ok_table.changes, SavedTable::File(filename.clone())));
+            filename
+        };
+        manifest.push((ok_table.name.clone(), filename));
+    }
+    saved_tables.retain(|tablename, _| manifest.iter().any(|entry| &entry.0 == tablename));
+    // Written next to the old manifest first, then replacing it at once.
+    let temporary = storage.path.join(format!("{}.tmp", MANIFEST));
+    write_neithdb_bytes(storage, &temporary, encode_manifest(&manifest))?;
+    fs::rename(&temporary, storage.path.join(MANIFEST))?;
+    for entry in fs::read_dir(&storage.path)? {
+        let filename = entry?.file_name().to_string_lossy().to_string();
+        if table_file_number(&filename).is_some() && !manifest.iter().any(|entry| entry.1 == filename) {
+            fs::remove_file(storage.path.join(filename))?;
+        }
+    }
+    return Ok(Success::SuccessMessage(true));
+}
+
+/// Reads every table listed in the supplied manifest out of its file.
+///
+/// ## Returns
+/// The tables in the order they were created, the format of their files and the file of each
+/// table, as if they were just saved.
+///
+/// ## Errors
+/// If the manifest is invalid, or a table file couldn't be read or doesn't hold its table.
+pub fn read_table_files(storage: &Storage, manifest: &[u8]) -> Result<(Vec<Table>, StorageFormat, SavedTables), Error> {
+    let mut tables: Vec<Table> = Vec::new();
+    let mut format = storage.format;
+    let mut saved_tables = SavedTables::new();
+    for (tablename, filename) in decode_manifest(manifest)? {
+        let bytes = decompress_neithdb_bytes(read_neithdb_bytes(storage, &storage.path.join(&filename))?)?;
+        let (mut file_tables, file_format) = decode_neithdb_tables(&bytes)?;
+        if file_tables.len() != 1 || file_tables[0].name != tablename {
+            return Err(Error::new(ErrorKind::InvalidData, format!("The file {} doesn't hold the table {}!", filename, tablename)));
+        }
+        tables.push(file_tables.remove(0));
+        format = file_format;
+        let _ = saved_tables.insert(tablename, (0, SavedTable::File(filename)));
+    }
+    return Ok((tables, format, saved_tables));
+}
+
+/// Recomputes the checksum of every table file listed in the manifest, see
+/// `verify_neithdb_bytes`. A file that couldn't be read counts as not matching.
+///
+/// ## Errors
+/// If the manifest couldn't be read or is invalid.
+pub fn verify_table_files(storage: &Storage) -> Result<Vec<(String, Option<bool>)>, Error> {
+    let manifest = decompress_neithdb_bytes(read_neithdb_bytes(storage, &storage.path.join(MANIFEST))?)?;
+    let mut out: Vec<(String, Option<bool>)> = Vec::new();
+    for (tablename, filename) in decode_manifest(&manifest)? {
+        let verified = read_neithdb_bytes(storage, &storage.path.join(&filename)).and_then(decompress_neithdb_bytes).and_then(|bytes| verify_neithdb_bytes(&bytes));
+        match verified {
+            Ok(tables) => out.extend(tables),
+            Err(_) => out.push((tablename, Some(false))),
+        }
+    }
+    return Ok(out);
+}
+
+/// Removes the manifest and every table file, and the directory once it is empty.
+///
+/// ## Errors
+/// If a file couldn't be removed, or the directory holds other files.
+pub fn remove_table_files(path: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(path)? {
+        let filename = entry?.file_name().to_string_lossy().to_string();
+        if filename == MANIFEST || table_file_number(&filename).is_some() {
+            fs::remove_file(path.join(filename))?;
+        }
+    }
+    return fs::remove_dir(path);
+}
+
+/// Decodes every table out of the contents of a `.neithdb` file, in either format.
+///
+/// ## Returns
+/// The tables, along with the format of the file.
+///
+/// ## Errors
+/// If the contents aren't a valid neithdb file.
+pub fn decode_neithdb_tables(bytes: &[u8]) -> Result<(Vec<Table>, StorageFormat), Error> {
+    if binard::is_binary(bytes) {
+        return Ok((binard::decode_tables(bytes)?, StorageFormat::Binary));
+    }
+    let read_file = upgrade_json(parse_neithdb_json(bytes)?)?;
+    let tables: Vec<Table> = read_file.entries().map(Table::from).collect();
+    return Ok((tables, StorageFormat::Json));
+}
+
+/// Recomputes the checksum of every table in the contents of a `.neithdb` file, in either format.
+///
+/// ## Returns
+/// The name of every table, along with `None` if the file has no checksum for it, or
+/// `Some(true)` if the checksums match.
+///
+/// ## Errors
+/// If the contents aren't a valid neithdb file.
+pub fn verify_neithdb_bytes(bytes: &[u8]) -> Result<Vec<(String, Option<bool>)>, Error> {
+    if binard::is_binary(bytes) {
+        return binard::verify_checksums(bytes);
+    }
+    return jisard::verify_checksums(bytes);
+}
+
+/// Encodes the name and file of every table as json.
+fn encode_manifest(manifest: &[(String, String)]) -> Vec<u8> {
+    let mut out = JsonValue::new_object();
+    out["neith"]["version"] = JsonValue::from(MANIFEST_VERSION);
+    out["neith"]["metadata"]["written by"] = JsonValue::from(format!("neith {}", env!("CARGO_PKG_VERSION")));
+    out["neith"]["metadata"]["saved"] = JsonValue::from(chrono::Utc::now().to_rfc3339());
+    let mut tables = JsonValue::new_array();
+    for (tablename, filename) in manifest {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = JsonValue::from(tablename.as_str());
+        entry["file"] = JsonValue::from(filename.as_str());
+        let _ = tables.push(entry);
+    }
+    out["tables"] = tables;
+    return out.dump().into_bytes();
+}
+
+/// Decodes the contents of a manifest.
+///
+/// ## Returns
+/// The name and file of every table, in the order they were created.
+///
+/// ## Errors
+/// If the manifest isn't valid json, was written by a newer version of Neith, or names a file
+/// that isn't a table file.
+fn decode_manifest(bytes: &[u8]) -> Result<Vec<(String, String)>, Error> {
+    let manifest = parse_neithdb_json(bytes)?;
+    let version = manifest["neith"]["version"].as_u32();
+    if version.is_none() {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a neithdb manifest!"));
+    }
+    if version.unwrap() > MANIFEST_VERSION {
+        return Err(Error::new(ErrorKind::InvalidData, format!("Manifest version {} is newer than the supported version {}!", version.unwrap(), MANIFEST_VERSION)));
+    }
+    let mut out: Vec<(String, String)> = Vec::new();
+    for entry in manifest["tables"].members() {
+        let tablename = entry["name"].as_str();
+        let filename = entry["file"].as_str();
+        // Only table files inside the directory are ever read.
+        if tablename.is_none() || filename.is_none() || table_file_number(filename.unwrap()).is_none() {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid table {} in the neithdb manifest!", entry.dump())));
+        }
+        out.push((tablename.unwrap().to_string(), filename.unwrap().to_string()));
+    }
+    return Ok(out);
+}
+
+/// Returns the number of a table file, named `table{number}.neithdb`.
+fn table_file_number(filename: &str) -> Option<u64> {
+    return filename.strip_prefix("table").and_then(|rest| rest.strip_suffix(".neithdb")).and_then(|number| number.parse::<u64>().ok());
+}
+
+/// Returns the number following the one of every table file in the directory.
+///
+/// ## Errors
+/// If the directory couldn't be read.
+fn next_file_number(path: &Path) -> Result<u64, Error> {
+    let mut out: u64 = 0;
+    for entry in fs::read_dir(path)? {
+        if let Some(number) = table_file_number(&entry?.file_name().to_string_lossy()) {
+            out = out.max(number + 1);
+        }
+    }
+    return Ok(out);
+}
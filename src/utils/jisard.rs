@@ -2,57 +2,147 @@
 //! It contains the logic for reading and writing a json file.
 
 use json::*;
-use std::{io::Read, fs::{File, self}, path::Path};
+use std::{fs, path::Path};
 
-use crate::{Neith, data::Data, success::Success};
+use crate::{database::{Database, Storage}, data::Data, success::Success, table::Table, utils::util::checksum};
+#[cfg(feature = "encryption")]
+use crate::utils::cryptard;
+#[cfg(feature = "compression")]
+use crate::utils::zipard;
 
-/// Takes a path and reads the json file at the location the path points to.
+/// Reads the contents of the file at the supplied path. If the file is encrypted, it is decrypted
+/// with the key of the storage settings.
+///
+/// ## Errors
+/// If the file couldn't be read, or is encrypted and the key is missing or wrong.
+#[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+pub fn read_neithdb_bytes(storage: &Storage, path: &Path) -> std::result::Result<Vec<u8>, std::io::Error> {
+    let bytes = fs::read(path)?;
+    #[cfg(feature = "encryption")]
+    if cryptard::is_encrypted(&bytes) {
+        if storage.key.is_none() {
+            return Err(std::io::Error::other("The neithdb file is encrypted! Connect with `connect_encrypted` instead."));
+        }
+        return cryptard::decrypt(storage.key.as_ref().unwrap(), &bytes);
+    }
+    return Ok(bytes);
+}
+
+/// Decompresses the contents of a file read by `read_neithdb_bytes`, if they are compressed.
+///
+/// ## Errors
+/// If the contents are compressed, but not valid gzip or the `compression` feature is disabled.
+pub fn decompress_neithdb_bytes(bytes: Vec<u8>) -> std::result::Result<Vec<u8>, std::io::Error> {
+    #[cfg(feature = "compression")]
+    if zipard::is_compressed(&bytes) {
+        return zipard::decompress(&bytes);
+    }
+    // Neither format starts with the gzip magic bytes.
+    #[cfg(not(feature = "compression"))]
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return Err(std::io::Error::other("The neithdb file is compressed! Enable the `compression` feature to read it."));
+    }
+    return Ok(bytes);
+}
+
+/// Writes the contents to the file at the supplied path. If compression is turned on in the
+/// storage settings they are compressed, and if there is a key, encrypted with it afterwards.
+///
+/// ## Errors
+/// If the contents couldn't be compressed or encrypted, or during saving to disc.
+#[cfg_attr(not(any(feature = "encryption", feature = "compression")), allow(unused_variables))]
+pub fn write_neithdb_bytes(storage: &Storage, path: &Path, contents: Vec<u8>) -> std::result::Result<(), std::io::Error> {
+    #[cfg(feature = "compression")]
+    let contents = if storage.compression { zipard::compress(&contents)? } else { contents };
+    #[cfg(feature = "encryption")]
+    if storage.key.is_some() {
+        return fs::write(path, cryptard::encrypt(storage.key.as_ref().unwrap(), &contents)?);
+    }
+    return fs::write(path, contents);
+}
+
+/// Parses the contents of a json file.
+///
+/// ## Errors
+/// If the contents aren't valid json.
+pub fn parse_neithdb_json(bytes: &[u8]) -> std::result::Result<JsonValue, std::io::Error> {
+    return parse(&String::from_utf8_lossy(bytes)).map_err(std::io::Error::other);
+}
+
+/// The version of the json format written by this version of Neith.
+/// - 1: A bare object of tables.
+/// - 2: An object with a `neith` header, holding the version, metadata and the checksum of each
+///   table, and the `tables`.
+pub const JSON_VERSION: u32 = 2;
+
+/// Returns the version of the json format the file is in.
+pub fn json_version(file: &JsonValue) -> u32 {
+    // A table called `neith` of a version 1 file holds column objects, never a number.
+    if file["neith"]["version"].is_number() && file["tables"].is_object() {
+        return file["neith"]["version"].as_u32().unwrap_or(u32::MAX);
+    }
+    return 1;
+}
+
+/// Recomputes the checksum of every table in the contents of a json file, and compares it to
+/// the one saved in the header. The checksum is taken over the compact json of the table, so
+/// only changes to the contents are found.
 ///
 /// ## Returns
-/// Returns a `JsonValue`.
-pub fn read_json_from_neithdb_file<P>(filename: P) -> JsonValue where P: AsRef<Path> {
-    let mut input = File::open(filename).expect("Unable to open file!");
-    let mut buffer = String::new();
-    let _ = input.read_to_string(&mut buffer);
-    let out = parse(&buffer).expect("Invalid json file!");
-    return out;
+/// The name of every table, along with `None` if the file has no checksum for it, or
+/// `Some(true)` if the checksums match.
+///
+/// ## Errors
+/// If the contents aren't valid json, or were written by a newer version of Neith.
+pub fn verify_checksums(bytes: &[u8]) -> std::result::Result<Vec<(String, Option<bool>)>, std::io::Error> {
+    let file = parse_neithdb_json(bytes)?;
+    let checksums = file["neith"]["checksums"].clone();
+    let tables = upgrade_json(file)?;
+    let mut out: Vec<(String, Option<bool>)> = Vec::new();
+    for (tablename, table) in tables.entries() {
+        let expected = checksums[tablename].as_u32();
+        out.push((tablename.to_string(), expected.map(|expected| checksum(table.dump().as_bytes()) == expected)));
+    }
+    return Ok(out);
 }
 
-/// Takes the database and writes it to file.
+/// Upgrades a json file of any older version to the current one, one version at a time.
+///
+/// ## Returns
+/// The tables of the file, as an object.
+///
+/// ## Errors
+/// If the file was written by a newer version of Neith.
+pub fn upgrade_json(file: JsonValue) -> std::result::Result<JsonValue, std::io::Error> {
+    let mut out = file;
+    loop {
+        match json_version(&out) {
+            1 => {
+                let mut upgraded = JsonValue::new_object();
+                upgraded["neith"]["version"] = JsonValue::from(2);
+                upgraded["neith"]["metadata"] = JsonValue::new_object();
+                upgraded["tables"] = out;
+                out = upgraded;
+            },
+            JSON_VERSION => return Ok(out["tables"].take()),
+            version => return Err(std::io::Error::other(format!("Json format version {} is newer than the supported version {}!", version, JSON_VERSION))),
+        }
+    }
+}
+
+/// Takes the database and writes it to file, as the storage settings say.
 ///
 /// ## Returns
 /// A generic Success message.
 ///
 /// ## Errors
 /// Can `JsonError` during json encoding or saving to disc.
-pub fn write_neithdb_file(neith: Neith) -> Result<Success> {
-    let mut json_tables = JsonValue::new_object();
-    for tmp_table in &neith.tables {
-        let table_store = tmp_table.lock();
-        if table_store.is_ok() {
-            let table = table_store.unwrap();
-            let tablename = &table.name;
-            let mut json_table = JsonValue::new_object();
-            for column in &table.columns {
-                let columnname = &column.name;
-                let unique = column.unique;
-                let mut data_array = JsonValue::new_array();
-                for data in &column.contents.all_row_data {
-                    let _answ = data_array.push(decode_data_to_jsonval(data.clone()))?;
-                }
-                let mut json_column = JsonValue::new_object();
-                let _answ0 = json_column.insert("unique", JsonValue::Boolean(unique))?;
-                let _answ1 = json_column.insert("entry", data_array)?;
-                let _answ3 = json_table.insert(&columnname, json_column)?;
-            }
-            let _answ2 = json_tables.insert(&tablename, json_table)?;
-        } else {
-            return Err(JsonError::WrongType("Couldn't lock table!".to_string()));
-        }
-        
+pub fn write_neithdb_file(database: &Database, storage: &Storage) -> Result<Success> {
+    let encoded_tables = database.encode_tables(encode_table_bytes);
+    if encoded_tables.is_err() {
+        return Err(JsonError::WrongType(encoded_tables.unwrap_err().to_string()));
     }
-    let file = fs::File::create(neith.path);
-    let fin = json_tables.write(&mut file.unwrap());
+    let fin = write_neithdb_bytes(storage, &storage.path, encode_neithdb_json(encoded_tables.unwrap()));
     if fin.is_ok() {
         return Ok(Success::SuccessMessage(true));
     } else {
@@ -60,12 +150,82 @@ pub fn write_neithdb_file(neith: Neith) -> Result<Success> {
     }
 }
 
+/// Encodes a table as compact json, the way it is written to file.
+///
+/// ## Errors
+/// If the table can't be encoded as json.
+pub fn encode_table_bytes(table: &Table) -> std::result::Result<Vec<u8>, std::io::Error> {
+    let json_table = encode_table(table).map_err(std::io::Error::other)?;
+    return Ok(json_table.dump().into_bytes());
+}
+
+/// Puts the header in front of the supplied tables, encoded by `encode_table_bytes`.
+///
+/// ## Returns
+/// The contents of a json file holding the tables, in the order they were supplied.
+pub fn encode_neithdb_json(encoded_tables: Vec<(String, Vec<u8>)>) -> Vec<u8> {
+    let mut header = JsonValue::new_object();
+    header["version"] = JsonValue::from(JSON_VERSION);
+    header["metadata"]["written by"] = JsonValue::from(format!("neith {}", env!("CARGO_PKG_VERSION")));
+    header["metadata"]["saved"] = JsonValue::from(chrono::Utc::now().to_rfc3339());
+    header["metadata"]["tables"] = JsonValue::from(encoded_tables.len());
+    header["checksums"] = JsonValue::new_object();
+    for (tablename, table) in &encoded_tables {
+        header["checksums"][tablename.as_str()] = JsonValue::from(checksum(table));
+    }
+    let mut json_tables: Vec<u8> = format!("{{\"neith\":{},\"tables\":{{", header.dump()).into_bytes();
+    for (position, (tablename, table)) in encoded_tables.into_iter().enumerate() {
+        if position > 0 {
+            json_tables.push(b',');
+        }
+        json_tables.extend(stringify(tablename).into_bytes());
+        json_tables.push(b':');
+        json_tables.extend(table);
+    }
+    json_tables.extend(b"}}");
+    return json_tables;
+}
+
+/// Encodes a table, its columns and its check constraints as a `JsonValue`.
+///
+/// ## Errors
+/// Can `JsonError` during json encoding.
+fn encode_table(table: &Table) -> Result<JsonValue> {
+    let mut json_table = JsonValue::new_object();
+    for column in &table.columns {
+        let columnname = &column.name;
+        let unique = column.unique;
+        let mut data_array = JsonValue::new_array();
+        for data in &column.contents.all_row_data {
+            let _answ = data_array.push(decode_data_to_jsonval(data.clone()))?;
+        }
+        let mut json_column = JsonValue::new_object();
+        let _answ0 = json_column.insert("unique", JsonValue::Boolean(unique))?;
+        let _answ1 = json_column.insert("entry", data_array)?;
+        if column.index.is_some() {
+            let _answ7 = json_column.insert("index", JsonValue::String(column.index.as_ref().unwrap().get_type()))?;
+        }
+        if column.references.is_some() {
+            let _answ4 = json_column.insert("references", column.references.as_ref().unwrap().to_json_value())?;
+        }
+        let _answ3 = json_table.insert(columnname, json_column)?;
+    }
+    if !table.checks.is_empty() {
+        let mut json_checks = JsonValue::new_array();
+        for check in &table.checks {
+            let _answ5 = json_checks.push(check.to_json_value())?;
+        }
+        let _answ6 = json_table.insert("check constraints", json_checks)?;
+    }
+    return Ok(json_table);
+}
+
 /// Takes in a `neith::Data` and encodes it as a `JsonValue`.
 /// This supports up to 5 nested lists!
 ///
 /// ## Returns
 /// A `JsonValue` containing the passed in `Data`.
-fn decode_data_to_jsonval(neith_data: crate::Data) -> JsonValue {
+pub fn decode_data_to_jsonval(neith_data: crate::Data) -> JsonValue {
     // Nested functions, now in a database near you!
     /// Converts the data to a `JsonValue`. Should a list be passed in, its set to `Null.`
     ///
@@ -1,2 +1,8 @@
 pub mod util;
 pub mod jisard;
+pub mod binard;
+pub mod dirard;
+#[cfg(feature = "encryption")]
+pub mod cryptard;
+#[cfg(feature = "compression")]
+pub mod zipard;
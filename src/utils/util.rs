@@ -1,8 +1,8 @@
 //! This contains general supporting logic, mainly for lib.rs
 
-use std::{path::PathBuf, io::Error};
+use std::{path::PathBuf, io::Error, collections::HashSet};
 
-use crate::{data::Data, table::Table};
+use crate::{index::IndexKey, data::Data, table::Table, column::Column, constraint::{ForeignKey, Check, OnConflict}, condition::{Condition, Operator}};
 
 /// Strips the leading word of a given string and returns a touple containing both.
 ///
@@ -22,11 +22,21 @@ pub fn strip_leading_word(to_strip: String) -> (String, String) {
 /// A touple [`(String, String)`] where first is the stripped leading condition list, followed by the rest of
 /// the string in second place.
 pub fn strip_condition_list(to_strip: String) -> (String, String) {
-    let split_query = to_strip.splitn(2, "]");
-    let mut condition_list = split_query.clone().take(1).collect::<String>();
+    // The condition list of a subquery is part of the list.
+    let mut depth: usize = 0;
+    for (index, character) in to_strip.char_indices() {
+        if character == '[' {
+            depth += 1;
+        } else if character == ']' {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return (to_strip[..=index].to_string(), to_strip[index + 1..].trim_start().to_string());
+            }
+        }
+    }
+    let mut condition_list = to_strip;
     condition_list.push_str("]");
-    let remainder = split_query.skip(1).collect::<String>().trim_start().to_string();
-    return (condition_list, remainder);
+    return (condition_list, String::new());
 }
 
 /// Strips the leading column list of a given string and returns a touple containing both.
@@ -42,11 +52,8 @@ pub fn strip_column_list(to_strip: String) -> Result<(String, String), Error> {
         let answ = strip_leading_word(to_strip);
         return Ok(answ);
     } else if to_strip.starts_with("(") {
-        let split_query = to_strip.splitn(2, ")");
-        let mut condition_list = split_query.clone().take(1).collect::<String>();
-        condition_list.push_str(")");
-        let remainder = split_query.skip(1).collect::<String>().trim_start().to_string();
-        return Ok((condition_list, remainder));
+        // Functions in the list bring their own parenthesis.
+        return strip_enclosed_list(to_strip, '(', ')');
     } else {
         return Err(Error::other(format!("Invalid nql syntax; {:?} is not a column list (columnname, othercolumnname)", to_strip)));
     }
@@ -80,49 +87,167 @@ pub fn check_for_persistant_db(filename: PathBuf) -> bool {
 ///
 /// ## Returns
 /// A vector containing each column as a string.
-pub fn decode_column_list(input: String, table: Table) -> Vec<String> {
-    if input.contains("*") {
+///
+/// ## Errors
+/// If the parenthesis of the list don't match.
+pub fn decode_column_list(input: String, table: Table) -> Result<Vec<String>, Error> {
+    if input.trim() == "*" {
         let mut found_column: Vec<String> = Vec::new();
         for column in table.columns {
             found_column.push(column.name);
         }
-        return found_column;
+        return Ok(found_column);
     } else {
-        let no_parenthesis = input.replace("(", "").replace(")", "");
-        let column_names = no_parenthesis.split(",");
+        let no_parenthesis = strip_outer_parenthesis(&input)?;
+        let column_names = split_outside_parenthesis(no_parenthesis, ",");
         let mut out: Vec<String> = Vec::new();
         for name in column_names {
             out.push(name.trim().to_string());
         }
-        return out;
+        return Ok(out);
     }
     
 }
 
+/// Flattens the new data of every row of an update to a single list, every entry of a column
+/// is only kept once.
+///
+/// ## Returns
+/// A vector containing touples of [`(String, Data)`], the column name and one of its entries.
+pub fn distinct_entries(value: &[(String, Vec<Data>)]) -> Vec<(String, Data)> {
+    let mut out: Vec<(String, Data)> = Vec::new();
+    for (columnname, entries) in value {
+        let mut seen: HashSet<IndexKey> = HashSet::new();
+        for data in entries {
+            if seen.insert(IndexKey(data.clone())) {
+                out.push((columnname.clone(), data.clone()));
+            }
+        }
+    }
+    return out;
+}
+
+/// Strips the alias of an entry of a select list: `'columnname' as 'alias'`
+///
+/// ## Returns
+/// A touple [`(String, Option<String>)`] of the entry without its alias, followed by the alias
+/// if there is one.
+pub fn strip_alias(value: &str) -> (String, Option<String>) {
+    let parts = split_outside_parenthesis(value, " as ");
+    match parts.as_slice() {
+        [entry, alias] if !alias.trim().is_empty() && !alias.trim().contains(' ') => return (entry.trim().to_string(), Some(alias.trim().to_string())),
+        _ => return (value.trim().to_string(), None),
+    }
+}
+
+/// Splits the string by the pattern, except inside of parenthesis or single quotes.
+///
+/// ## Returns
+/// A vector containing every part, without the pattern.
+pub fn split_outside_parenthesis(value: &str, pattern: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut depth: usize = 0;
+    let mut quoted = false;
+    let mut start: usize = 0;
+    for (index, character) in value.char_indices() {
+        match character {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            _ if !quoted && depth == 0 && index >= start && value[index..].starts_with(pattern) => {
+                out.push(value[start..index].to_string());
+                start = index + pattern.len();
+            },
+            _ => {},
+        }
+    }
+    out.push(value[start..].to_string());
+    return out;
+}
+
+/// Finds the first subquery, a select statement in parenthesis, outside of single quotes.
+///
+/// ## Returns
+/// The position of its opening parenthesis, if there is one.
+pub fn find_subquery(value: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (index, character) in value.char_indices() {
+        match character {
+            '\'' => quoted = !quoted,
+            '(' if !quoted && value[index..].starts_with("(select ") => return Some(index),
+            _ => {},
+        }
+    }
+    return None;
+}
+
+/// Removes the parenthesis enclosing a whole list, if there are any. Parenthesis inside of single
+/// quotes are ignored.
+///
+/// ## Errors
+/// If a parenthesis is never closed, or closed without being opened.
+pub fn strip_outer_parenthesis(value: &str) -> Result<&str, Error> {
+    let trimmed = value.trim();
+    let mut depth: usize = 0;
+    let mut quoted = false;
+    // The position the parenthesis opened at the start are closed at.
+    let mut outer_close: Option<usize> = None;
+    for (index, character) in trimmed.char_indices() {
+        match character {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                if depth == 0 {
+                    return Err(Error::other(format!("Invalid nql syntax. {:?} closes a parenthesis that was never opened.", trimmed)));
+                }
+                depth -= 1;
+                if depth == 0 && outer_close.is_none() && trimmed.starts_with('(') {
+                    outer_close = Some(index);
+                }
+            },
+            _ => {},
+        }
+    }
+    if depth != 0 {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} opens a parenthesis that is never closed.", trimmed)));
+    }
+    if outer_close.is_some_and(|index| index == trimmed.len() - 1) {
+        return Ok(&trimmed[1..trimmed.len() - 1]);
+    }
+    return Ok(trimmed);
+}
+
 /// Decodes the colum-maker list from a string.
+/// A column is declared as `'columnname' 'unique'`, optionally followed by a foreign key:
+/// `references 'tablename'('columnname') on delete {cascade/restrict/set null}`
 ///
 /// ## Returns
-/// A vector containing the touple [`(String, bool)`], the string is the colum name, the boolean if
-/// it is unique or not.
+/// A vector containing the declared, still empty, columns.
 ///
 /// ## Errors
 /// Can error from invalid nql syntax.
-pub fn decode_columnmaker(input: String) -> Result<Vec<(String, bool)>, Error> {
+pub fn decode_columnmaker(input: String) -> Result<Vec<Column>, Error> {
     // ('columnname' 'unique, ...') is left.
-    let no_parenthesis = input.replace("(", "").replace(")", "");
+    // Only the outer parenthesis are removed, a foreign key brings its own.
+    let no_parenthesis = strip_outer_parenthesis(&input)?;
     let column_names = no_parenthesis.split(",");
-    let mut temp_column_bind: Vec<(String, bool)> = Vec::new();
+    let mut temp_column_bind: Vec<Column> = Vec::new();
     for name in column_names {
         let split_column = name.split_whitespace();
-        if split_column.clone().count() != 2 {
-            return Err(Error::other(format!("Invalid nql syntax. Wrong amount elements in column creation. {:?} => should contain the name and unique bool, nothing else.", name)));
+        if split_column.clone().count() < 2 || split_column.clone().count() == 3 {
+            return Err(Error::other(format!("Invalid nql syntax. Wrong amount elements in column creation. {:?} => should contain the name and unique bool, and optionally a foreign key.", name)));
         } else {
             let name = split_column.clone().take(1).collect::<String>();
-            let unique = split_column.clone().skip(1).collect::<String>().parse::<bool>();
+            let unique = split_column.clone().skip(1).take(1).collect::<String>().parse::<bool>();
             if unique.is_ok() {
-                temp_column_bind.push((name, unique.unwrap()));
+                let mut column = Column::from((name, unique.unwrap()));
+                if split_column.clone().count() > 2 {
+                    let reference = split_column.skip(2).collect::<Vec<&str>>().join(" ");
+                    column.references = Some(ForeignKey::from_nql(&reference)?);
+                }
+                temp_column_bind.push(column);
             } else {
-                return Err(Error::other(format!("Invalid nql syntax. Could not parse {} into a boolean!", split_column.skip(1).collect::<String>())));
+                return Err(Error::other(format!("Invalid nql syntax. Could not parse {} into a boolean!", split_column.skip(1).take(1).collect::<String>())));
             }
         }
     }
@@ -138,12 +263,10 @@ pub fn decode_columnmaker(input: String) -> Result<Vec<(String, bool)>, Error> {
 /// encoded value read from the input list.
 pub fn decode_list_columndata(list_val: String, split_pattern: String) -> Vec<(String, Data)> {
     let mut out: Vec<(String, Data)> = Vec::new();
-    let mut clean_in = list_val.replacen("(", "", 1);
-    if clean_in.ends_with("))") {
-        clean_in = clean_in.replacen("))", ")", 1);
-    } else {
-        clean_in = clean_in.trim_end_matches(")").to_string();
-    }
+    // Only the outer parenthesis are removed, lists and functions bring their own.
+    let trimmed = list_val.trim();
+    let no_prefix = trimmed.strip_prefix("(").unwrap_or(trimmed);
+    let clean_in = no_prefix.strip_suffix(")").unwrap_or(no_prefix);
     let split = clean_in.split(&split_pattern);
     let mut list_check = false;
     for entry in split {
@@ -181,7 +304,7 @@ pub fn decode_single_columndata(single_val: &str, split_pattern: &str) -> (Strin
     return (name, data);
 }
 
-/// Decodes list conditions of this general shema: `['columnname' = 'data', {and/not/or} 'other_columnname' = 'other data', ...]` with each entry separated by a supplied split pattern, to a temporary format for further decoding.
+/// Decodes list conditions of this general shema: `['columnname' = 'data', {and/not/or/xor} 'other_columnname' = 'other data', ...]` with each entry separated by a supplied split pattern, to a temporary format for further decoding.
 /// 
 ///
 /// ## Returns
@@ -194,16 +317,18 @@ pub fn decode_list_conditions(value: String, split_pattern: String) -> Result<Ve
     let split = cleaned_value.split(split_pattern.as_str());
     let mut out: Vec<String> = Vec::new();
     for entry in split.clone() {
-        if entry.starts_with(" and") || entry.starts_with(" not") || entry.starts_with(" or") {
+        if entry.starts_with(" and ") || entry.starts_with(" not ") || entry.starts_with(" or ") || entry.starts_with(" xor ") {
             let input = entry.trim_start().splitn(2, " ");
             let condition = input.clone().take(1).collect::<String>();
             out.push(condition);
             let pair = input.skip(1).collect::<String>();
             out.push(pair);
-        } else if entry.contains(" = ") {
+        } else if entry.split_whitespace().nth(1).and_then(Operator::from_nql).is_some() {
+            out.push(entry.to_string());
+        } else if entry.split_whitespace().next() == Some("exists") {
             out.push(entry.to_string());
         } else {
-            return Err(Error::other(format!("Invalid nql syntax. Only 'column = data' pairs or conditionals! {:?}", split)));
+            return Err(Error::other(format!("Invalid nql syntax. Only 'column operator data' pairs or conditionals! {:?}", split)));
         }
     }
     return Ok(out);
@@ -213,41 +338,229 @@ pub fn decode_list_conditions(value: String, split_pattern: String) -> Result<Ve
 /// 
 ///
 /// ## Returns
-/// A vector containing a touple [`(String, Data)`], where `String` is the column name OR
-/// condition. `Data` holds the data for the column if the `String` is the column name, or holds
-/// `Data::default()` if the `String` contains the condition.
+/// A vector containing a `Condition` for each entry, either comparing a column with data, or a
+/// connector.
 ///
 /// ## Errors
 /// If supplied with invalid nql.
-pub fn encode_list_conditions(value: Vec<String>, split_pattern: String) -> Result<Vec<(String, Data)>, Error> {
-    let mut encoding_list: Vec<(String, Data)> = Vec::new();
+pub fn encode_list_conditions(value: Vec<String>, split_pattern: String) -> Result<Vec<Condition>, Error> {
+    let mut encoding_list: Vec<Condition> = Vec::new();
     for thing in &value {
-        let mut cleaned_thing = thing.to_owned();
-        if thing.contains("[") {
-            let temp = thing.replace("[", "");
-            cleaned_thing = temp;
-        } else if thing.contains("]") {
-            let temp = thing.replace("]", "");
-            cleaned_thing = temp;
-        }
-        if cleaned_thing.contains(" = ") {
-            let decode_columndata = decode_single_columndata(&cleaned_thing, &split_pattern);
-            let name = decode_columndata.0;
-            let data = decode_columndata.1;
-            encoding_list.push((name, data));
-        } else if cleaned_thing.contains("and") {
-            encoding_list.push((cleaned_thing, Data::default()));
-        } else if cleaned_thing.contains("not") {
-            encoding_list.push((cleaned_thing, Data::default()));
-        } else if cleaned_thing.contains("or") {
-            encoding_list.push((cleaned_thing, Data::default()));
-        } else {
-            return Err(Error::other(format!("Invalid nql syntax. This should be either a conditional or a single_columndata = {}", cleaned_thing)));
+        let cleaned_thing = thing.replace("[", "").replace("]", "");
+        match cleaned_thing.trim() {
+            "and" | "not" | "or" | "xor" => encoding_list.push(Condition::Connector(cleaned_thing.trim().to_string())),
+            "exists true" => encoding_list.push(Condition::Exists(true)),
+            "exists false" => encoding_list.push(Condition::Exists(false)),
+            _ => encoding_list.push(decode_single_condition(&cleaned_thing, &split_pattern)?),
         }
     }
     return Ok(encoding_list);
 }
 
+/// Decodes a singular condition of `columnname operator data` passed in as a `&str`.
+/// Supported operators are `=`, `!=`, `<`, `<=`, `>`, `>=` and `in`, `in` takes a list
+/// separated by `,`: `status in (open, closed)`
+///
+/// ## Returns
+/// A `Condition::Compare`.
+///
+/// ## Errors
+/// If no operator is found.
+pub fn decode_single_condition(single_val: &str, split_pattern: &str) -> Result<Condition, Error> {
+    let split_input = single_val.split_whitespace();
+    let name = split_input.clone().take(1).collect::<String>();
+    let operator = split_input.clone().nth(1).and_then(Operator::from_nql);
+    if operator.is_none() {
+        return Err(Error::other(format!("Invalid nql syntax. This should be either a conditional or a 'column operator data' pair = {}", single_val)));
+    }
+    let raw_data = split_input.skip(2).map(|d| format!("{} ", d)).collect::<String>().trim_end().to_string();
+    let data = match operator {
+        Some(Operator::In) => Data::from(raw_data, ",".to_string()),
+        _ => Data::from(raw_data, split_pattern.to_string()),
+    };
+    return Ok(Condition::Compare(name, operator.unwrap(), data));
+}
+
+/// Strips a trailing `order by 'columnname' {asc/desc}` of a given string, `asc` is the default.
+///
+/// ## Returns
+/// A touple [`(String, Option<(String, bool)>)`] where first is the rest of the string, followed
+/// by the column name and if the order is descending; if there is an `order by`.
+///
+/// ## Errors
+/// If the `order by` is not followed by a column name and optionally the direction.
+pub fn strip_order_by(to_strip: String) -> Result<(String, Option<(String, bool)>), Error> {
+    let position = to_strip.rfind("order by ");
+    // An `order by` of a subquery is followed by its closing parenthesis.
+    if position.is_none() || !(position == Some(0) || to_strip[..position.unwrap()].ends_with([' ', ']'])) || to_strip[position.unwrap()..].contains([')', ']']) {
+        return Ok((to_strip, None));
+    }
+    let remainder = to_strip[..position.unwrap()].trim_end().to_string();
+    let order: Vec<&str> = to_strip[position.unwrap() + 9..].split_whitespace().collect();
+    match order.as_slice() {
+        [column] => return Ok((remainder, Some((column.to_string(), false)))),
+        [column, "asc"] => return Ok((remainder, Some((column.to_string(), false)))),
+        [column, "desc"] => return Ok((remainder, Some((column.to_string(), true)))),
+        _ => return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'order by columnname {{asc/desc}}'", &to_strip[position.unwrap()..]))),
+    }
+}
+
+/// The rows of a new data statement, along with the column and behaviour of its `on conflict`.
+pub type NewRows = (Vec<Vec<(String, Data)>>, Option<(String, OnConflict)>);
+
+/// Decodes every row of a new data statement, the remainder following its table name:
+/// `('columnname' = 'data',+ ...), ('columnname' = 'data',+ ...) {on conflict ...}`
+///
+/// ## Returns
+/// A touple of every decoded row, followed by the decoded `on conflict`, if there is one.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_new_rows(value: String, split_pattern: String) -> Result<NewRows, Error> {
+    let mut rows: Vec<Vec<(String, Data)>> = Vec::new();
+    let mut remainder = value;
+    loop {
+        let (row, rest) = strip_new_row(remainder);
+        rows.push(decode_list_columndata(row, split_pattern.clone()));
+        match rest.strip_prefix(",") {
+            Some(next) => remainder = next.to_string(),
+            None => return Ok((rows, decode_on_conflict(rest)?)),
+        }
+    }
+}
+
+/// Strips the leading row of a new data statement. Text may contain parenthesis of its own, so a
+/// row only ends at a `)` followed by nothing, another row or `on conflict`. The `)` closing the
+/// first `(` is tried first.
+///
+/// ## Returns
+/// A touple [`(String, String)`] of the row including its parenthesis, followed by the rest of
+/// the string. If no `)` fits, the whole string is the row.
+fn strip_new_row(value: String) -> (String, String) {
+    let trimmed = value.trim();
+    let ends_row = |rest: &str| -> bool {
+        let rest = rest.trim_start();
+        let next_row = rest.strip_prefix(",").is_some_and(|next| next.trim_start().starts_with("("));
+        return rest.is_empty() || next_row || rest.starts_with("on conflict ");
+    };
+    let mut candidates: Vec<usize> = Vec::new();
+    if let Ok((row, _)) = strip_enclosed_list(trimmed.to_string(), '(', ')') {
+        candidates.push(row.len() - 1);
+    }
+    candidates.extend(trimmed.match_indices(")").map(|(index, _)| index));
+    for index in candidates {
+        if ends_row(&trimmed[index + 1..]) {
+            return (trimmed[..=index].to_string(), trimmed[index + 1..].trim_start().to_string());
+        }
+    }
+    return (trimmed.to_string(), String::new());
+}
+
+/// Decodes what happens to new data conflicting with an existing row, the remainder of a new
+/// data statement following its list: `on conflict ('columnname') {update/ignore}`
+///
+/// ## Returns
+/// The column name and the behaviour, `None` if the remainder is empty.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_on_conflict(value: String) -> Result<Option<(String, OnConflict)>, Error> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    let remainder = value.trim().strip_prefix("on conflict ");
+    if remainder.is_none() {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'on conflict (columnname) {{update/ignore}}'", value)));
+    }
+    let (column, action) = strip_enclosed_list(remainder.unwrap().to_string(), '(', ')')?;
+    let columnname = column[1..column.len() - 1].trim().to_string();
+    return Ok(Some((columnname, OnConflict::from_nql(&action)?)));
+}
+
+/// Decodes a column of a table written as `'tablename'('columnname')`.
+///
+/// ## Returns
+/// A touple [`(String, String)`] of the table name and the column name.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_table_column(value: &str) -> Result<(String, String), Error> {
+    let trimmed = value.trim();
+    if !trimmed.contains("(") || !trimmed.ends_with(")") {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'tablename(columnname)'", trimmed)));
+    }
+    let split_reference = trimmed.trim_end_matches(")").splitn(2, "(");
+    let table = split_reference.clone().take(1).collect::<String>();
+    let column = split_reference.skip(1).collect::<String>();
+    if table.is_empty() || column.is_empty() {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'tablename(columnname)'", trimmed)));
+    }
+    return Ok((table, column));
+}
+
+/// Strips the leading enclosed list of a given string, e.g. everything up to and including the
+/// `)` closing the first `(`. Nested enclosures are kept intact.
+///
+/// ## Returns
+/// A touple [`(String, String)`] where first is the stripped list including its enclosing
+/// characters, followed by the rest of the string in second place.
+///
+/// ## Errors
+/// If the string does not start with the opening character, or it is never closed.
+pub fn strip_enclosed_list(to_strip: String, open: char, close: char) -> Result<(String, String), Error> {
+    let trimmed = to_strip.trim_start();
+    if !trimmed.starts_with(open) {
+        return Err(Error::other(format!("Invalid nql syntax. {:?} should start with '{}'", trimmed, open)));
+    }
+    let mut depth: usize = 0;
+    for (index, character) in trimmed.char_indices() {
+        if character == open {
+            depth += 1;
+        } else if character == close {
+            depth -= 1;
+            if depth == 0 {
+                let list = trimmed[..=index].to_string();
+                let remainder = trimmed[index + 1..].trim_start().to_string();
+                return Ok((list, remainder));
+            }
+        }
+    }
+    return Err(Error::other(format!("Invalid nql syntax. {:?} is missing a closing '{}'", trimmed, close)));
+}
+
+/// Decodes the check constraints following the column list of a new table:
+/// `check ['columnname' >= 'data',+ ...] check 'checkname' ['columnname' = 'data']`
+/// Checks without a name are named after the table and their position.
+///
+/// ## Returns
+/// A vector containing the decoded checks.
+///
+/// ## Errors
+/// If supplied with invalid nql.
+pub fn decode_checks(input: String, tablename: &String, split_pattern: String) -> Result<Vec<Check>, Error> {
+    let mut out: Vec<Check> = Vec::new();
+    let mut remainder = input.trim().to_string();
+    while !remainder.is_empty() {
+        let keyword = strip_leading_word(remainder);
+        if keyword.0 != "check" {
+            return Err(Error::other(format!("Invalid nql syntax. {:?} should be 'check'", keyword.0)));
+        }
+        let mut name = format!("{}_check_{}", tablename, out.len());
+        let mut rest = keyword.1.trim_start().to_string();
+        if !rest.starts_with("[") {
+            let named = strip_leading_word(rest);
+            name = named.0;
+            rest = named.1;
+        }
+        let condition_list = strip_enclosed_list(rest, '[', ']')?;
+        let decoded_conditions = decode_list_conditions(condition_list.0, split_pattern.clone())?;
+        let conditions = encode_list_conditions(decoded_conditions, split_pattern.clone())?;
+        out.push(Check { name, conditions });
+        remainder = condition_list.1;
+    }
+    return Ok(out);
+}
+
 /// Takes in two vectors of `usize` and a condition.
 /// Then checks both vectors against each other using the supplied condition.
 /// 
@@ -315,3 +628,27 @@ pub fn condition_check(search: Vec<usize>, condition: String, other_search: Vec<
     return Ok(found_data);
 }
 
+
+/// Calculates the CRC-32 (as used by zip and png) checksum of the supplied bytes.
+pub fn checksum(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut entry = 0;
+        while entry < 256 {
+            let mut crc = entry as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+                bit += 1;
+            }
+            table[entry] = crc;
+            entry += 1;
+        }
+        table
+    };
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc = TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    return !crc;
+}
@@ -0,0 +1,34 @@
+//! This is the Zip-Wizard or Zipard for short.
+//! It contains the logic for compressing and decompressing a `.neithdb` file with gzip.
+
+use std::io::{Error, Read, Write};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+/// Every gzip file starts with these bytes.
+pub const MAGIC_BYTES: &[u8; 2] = &[0x1f, 0x8b];
+
+/// Checks if the supplied file contents are compressed.
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    return bytes.starts_with(MAGIC_BYTES);
+}
+
+/// Compresses the contents of a file.
+///
+/// ## Errors
+/// If the contents couldn't be compressed.
+pub fn compress(plain: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(plain)?;
+    return encoder.finish();
+}
+
+/// Decompresses the contents of a compressed file.
+///
+/// ## Errors
+/// If the contents aren't valid gzip.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out: Vec<u8> = Vec::new();
+    let _ = GzDecoder::new(bytes).read_to_end(&mut out)?;
+    return Ok(out);
+}